@@ -295,6 +295,7 @@ impl AuthenticationPrompt {
             font_size: rems(0.875).into(),
             font_weight: settings.ui_font.weight,
             font_style: FontStyle::Normal,
+            font_width: settings.ui_font.width,
             line_height: relative(1.3),
             background_color: None,
             underline: None,
@@ -18,8 +18,8 @@ use editor::{
 use fs::Fs;
 use futures::{channel::mpsc, SinkExt, Stream, StreamExt};
 use gpui::{
-    point, AppContext, EventEmitter, FocusHandle, FocusableView, FontStyle, FontWeight, Global,
-    HighlightStyle, Model, ModelContext, Subscription, Task, TextStyle, UpdateGlobal, View,
+    point, AppContext, EventEmitter, FocusHandle, FocusableView, FontStyle, FontWeight, FontWidth,
+    Global, HighlightStyle, Model, ModelContext, Subscription, Task, TextStyle, UpdateGlobal, View,
     ViewContext, WeakView, WhiteSpace, WindowContext,
 };
 use language::{Buffer, Point, Selection, TransactionId};
@@ -1732,6 +1732,7 @@ impl PromptEditor {
             font_size: rems(0.875).into(),
             font_weight: FontWeight::NORMAL,
             font_style: FontStyle::Normal,
+            font_width: FontWidth::NORMAL,
             line_height: relative(1.3),
             background_color: None,
             underline: None,
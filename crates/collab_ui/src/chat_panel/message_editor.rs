@@ -536,6 +536,7 @@ impl Render for MessageEditor {
             font_size: TextSize::Small.rems(cx).into(),
             font_weight: settings.ui_font.weight,
             font_style: FontStyle::Normal,
+            font_width: settings.ui_font.width,
             line_height: relative(1.3),
             background_color: None,
             underline: None,
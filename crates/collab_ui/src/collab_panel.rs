@@ -2193,6 +2193,7 @@ impl CollabPanel {
             font_size: rems(0.875).into(),
             font_weight: settings.ui_font.weight,
             font_style: FontStyle::Normal,
+            font_width: settings.ui_font.width,
             line_height: relative(1.3),
             background_color: None,
             underline: None,
@@ -3,7 +3,7 @@ use super::{
     tab_map::{self, TabEdit, TabPoint, TabSnapshot},
     Highlights,
 };
-use gpui::{AppContext, Context, Font, LineWrapper, Model, ModelContext, Pixels, Task};
+use gpui::{AppContext, Context, Font, LineWrapper, Model, ModelContext, Pixels, Task, WrapMode};
 use language::{Chunk, Point};
 use lazy_static::lazy_static;
 use multi_buffer::MultiBufferSnapshot;
@@ -467,7 +467,11 @@ impl WrapSnapshot {
                     }
 
                     let mut prev_boundary_ix = 0;
-                    for boundary in line_wrapper.wrap_line(&line, wrap_width) {
+                    for boundary in line_wrapper.wrap_line(
+                        &line,
+                        wrap_width,
+                        WrapMode::WordBoundaryThenAnywhere,
+                    ) {
                         let wrapped = &line[prev_boundary_ix..boundary.ix];
                         push_isomorphic(&mut edit_transforms, TextSummary::from(wrapped));
                         edit_transforms.push(Transform::wrap(boundary.next_indent));
@@ -1293,7 +1297,9 @@ mod tests {
                 }
 
                 let mut prev_ix = 0;
-                for boundary in line_wrapper.wrap_line(line, wrap_width) {
+                for boundary in
+                    line_wrapper.wrap_line(line, wrap_width, WrapMode::WordBoundaryThenAnywhere)
+                {
                     wrapped_text.push_str(&line[prev_ix..boundary.ix]);
                     wrapped_text.push('\n');
                     wrapped_text.push_str(&" ".repeat(boundary.next_indent as usize));
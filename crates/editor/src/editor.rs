@@ -12111,6 +12111,7 @@ impl Render for Editor {
                 font_size: rems(0.875).into(),
                 font_weight: settings.ui_font.weight,
                 font_style: FontStyle::Normal,
+                font_width: settings.ui_font.width,
                 line_height: relative(settings.buffer_line_height.value()),
                 background_color: None,
                 underline: None,
@@ -12124,6 +12125,7 @@ impl Render for Editor {
                 font_size: settings.buffer_font_size(cx).into(),
                 font_weight: settings.buffer_font.weight,
                 font_style: FontStyle::Normal,
+                font_width: settings.buffer_font.width,
                 line_height: relative(settings.buffer_line_height.value()),
                 background_color: None,
                 underline: None,
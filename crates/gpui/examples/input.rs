@@ -244,16 +244,7 @@ impl ViewInputHandler for TextInput {
             return None;
         };
         let range = self.range_from_utf16(&range_utf16);
-        Some(Bounds::from_corners(
-            point(
-                bounds.left() + last_layout.x_for_index(range.start),
-                bounds.top(),
-            ),
-            point(
-                bounds.left() + last_layout.x_for_index(range.end),
-                bounds.bottom(),
-            ),
-        ))
+        Some(last_layout.bounds_for_range(range, bounds.origin, bounds.size.height))
     }
 }
 
@@ -315,38 +306,21 @@ impl Element for TextElement {
             underline: None,
             strikethrough: None,
         };
-        let runs = if let Some(marked_range) = input.marked_range.as_ref() {
-            vec![
-                TextRun {
-                    len: marked_range.start,
-                    ..run.clone()
-                },
-                TextRun {
-                    len: marked_range.end - marked_range.start,
-                    underline: Some(UnderlineStyle {
-                        color: Some(run.color),
-                        thickness: px(1.0),
-                        wavy: false,
-                    }),
-                    ..run.clone()
-                },
-                TextRun {
-                    len: input.content.len() - marked_range.end,
-                    ..run.clone()
-                },
-            ]
-            .into_iter()
-            .filter(|run| run.len > 0)
-            .collect()
-        } else {
-            vec![run]
-        };
-
         let font_size = style.font_size.to_pixels(cx.rem_size());
-        let line = cx
+        let mut line = cx
             .text_system()
-            .shape_line(content, font_size, &runs)
+            .shape_line(content, font_size, &[run])
             .unwrap();
+        if let Some(marked_range) = input.marked_range.clone() {
+            line = line.with_marked_range(
+                marked_range,
+                UnderlineStyle {
+                    color: None,
+                    thickness: px(1.0),
+                    wavy: false,
+                },
+            );
+        }
 
         let cursor_pos = line.x_for_index(cursor);
         let (selection, cursor) = if selected_range.is_empty() {
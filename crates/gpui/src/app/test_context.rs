@@ -4,11 +4,12 @@ use crate::{
     Element, Empty, Entity, EventEmitter, ForegroundExecutor, Global, InputEvent, Keystroke, Model,
     ModelContext, Modifiers, ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent,
     MouseUpEvent, Pixels, Platform, Point, Render, Result, Size, Task, TestDispatcher,
-    TestPlatform, TestWindow, TextSystem, View, ViewContext, VisualContext, WindowBounds,
-    WindowContext, WindowHandle, WindowOptions,
+    TestPlatform, TestTextSystem, TestWindow, TextSystem, View, ViewContext, VisualContext,
+    WindowBounds, WindowContext, WindowHandle, WindowOptions,
 };
 use anyhow::{anyhow, bail};
 use futures::{channel::oneshot, Stream, StreamExt};
+use image::RgbaImage;
 use std::{cell::RefCell, future::Future, ops::Deref, rc::Rc, sync::Arc, time::Duration};
 
 /// A TestAppContext is provided to tests created with `#[gpui::test]`, it provides
@@ -248,6 +249,17 @@ impl TestAppContext {
         &self.text_system
     }
 
+    /// Replaces the text system with one whose single built-in font has the same metrics on
+    /// every machine, so that tests of text layout can assert exact pixel values. Every font
+    /// resolves to it. Its ascent and descent are 0.8 and 0.2 em, and its characters advance
+    /// by half an em, or a whole em for CJK characters and emoji. This should be called
+    /// before any windows are opened.
+    pub fn use_test_text_system(&mut self) {
+        let text_system = Arc::new(TextSystem::new(Arc::new(TestTextSystem)));
+        self.app.borrow_mut().text_system = text_system.clone();
+        self.text_system = text_system;
+    }
+
     /// Simulates writing to the platform clipboard
     pub fn write_to_clipboard(&self, item: ClipboardItem) {
         self.test_platform.write_to_clipboard(item)
@@ -762,6 +774,18 @@ impl VisualTestContext {
         self.simulate_window_resize(self.window, size)
     }
 
+    /// Resizes the window, draws it, and renders the frame offscreen on the CPU, returning an
+    /// image in device pixels. This is meant for comparing frames against reference images, so
+    /// it follows the GPU shaders but isn't pixel-exact with them. Surfaces aren't rendered.
+    pub fn render_to_image(&mut self, size: Size<Pixels>) -> RgbaImage {
+        self.simulate_resize(size);
+        let window = self.test_window(self.window);
+        self.update(|cx| {
+            cx.draw();
+            window.render_to_image(&cx.window.rendered_frame.scene)
+        })
+    }
+
     /// debug_bounds returns the bounds of the element with the given selector.
     pub fn debug_bounds(&mut self, selector: &'static str) -> Option<Bounds<Pixels>> {
         self.update(|cx| cx.window.rendered_frame.debug_bounds.get(selector).copied())
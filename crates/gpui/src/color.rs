@@ -451,6 +451,15 @@ impl Hsla {
     pub fn fade_out(&mut self, factor: f32) {
         self.a *= 1.0 - factor.clamp(0., 1.);
     }
+
+    /// Returns a new HSLA color with the same hue, saturation, and lightness, with its alpha
+    /// multiplied by the given opacity. The opacity should be between 0.0 and 1.0.
+    pub fn opacity(&self, opacity: f32) -> Self {
+        Hsla {
+            a: self.a * opacity.clamp(0., 1.),
+            ..*self
+        }
+    }
 }
 
 impl From<Rgba> for Hsla {
@@ -1558,7 +1558,7 @@ impl Interactivity {
                         element_id.into(),
                         FONT_SIZE,
                         &[cx.text_style().to_run(str_len)],
-                        None,
+                        crate::WrapOptions::default(),
                     )
                     .ok()
                     .and_then(|mut text| text.pop())
@@ -1,8 +1,8 @@
 use crate::{
     ActiveTooltip, AnyTooltip, AnyView, Bounds, DispatchPhase, Element, ElementId, GlobalElementId,
     HighlightStyle, Hitbox, IntoElement, LayoutId, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
-    Pixels, Point, SharedString, Size, TextRun, TextStyle, WhiteSpace, WindowContext, WrappedLine,
-    TOOLTIP_DELAY,
+    Pixels, Point, SharedString, Size, TextRun, TextStyle, WhiteSpace, WindowContext, WrapOptions,
+    WrappedLine, TOOLTIP_DELAY,
 };
 use anyhow::anyhow;
 use parking_lot::{Mutex, MutexGuard};
@@ -294,7 +294,11 @@ impl TextLayout {
                         text.clone(),
                         font_size,
                         &runs,
-                        wrap_width, // Wrap if we know the width.
+                        // Wrap if we know the width.
+                        WrapOptions {
+                            width: wrap_width,
+                            ..Default::default()
+                        },
                     )
                     .log_err()
                 else {
@@ -454,6 +458,7 @@ pub struct InteractiveText {
     click_listener:
         Option<Box<dyn Fn(&[Range<usize>], InteractiveTextClickEvent, &mut WindowContext<'_>)>>,
     hover_listener: Option<Box<dyn Fn(Option<usize>, MouseMoveEvent, &mut WindowContext<'_>)>>,
+    range_hover_listener: Option<Box<dyn Fn(Option<usize>, &mut WindowContext<'_>)>>,
     tooltip_builder: Option<Rc<dyn Fn(usize, &mut WindowContext<'_>) -> Option<AnyView>>>,
     clickable_ranges: Vec<Range<usize>>,
 }
@@ -468,6 +473,7 @@ struct InteractiveTextClickEvent {
 pub struct InteractiveTextState {
     mouse_down_index: Rc<Cell<Option<usize>>>,
     hovered_index: Rc<Cell<Option<usize>>>,
+    hovered_range_ix: Rc<Cell<Option<usize>>>,
     active_tooltip: Rc<RefCell<Option<ActiveTooltip>>>,
 }
 
@@ -480,6 +486,7 @@ impl InteractiveText {
             text,
             click_listener: None,
             hover_listener: None,
+            range_hover_listener: None,
             tooltip_builder: None,
             clickable_ranges: Vec::new(),
         }
@@ -514,6 +521,17 @@ impl InteractiveText {
         self
     }
 
+    /// on_hover_range is called when the mouse moves onto or off of one of the ranges given to
+    /// [`Self::on_click`], passing the index of the hovered range, or None if no range is hovered.
+    /// This can be used to restyle the hovered range, e.g. to underline a link.
+    pub fn on_hover_range(
+        mut self,
+        listener: impl Fn(Option<usize>, &mut WindowContext<'_>) + 'static,
+    ) -> Self {
+        self.range_hover_listener = Some(Box::new(listener));
+        self
+    }
+
     /// tooltip lets you specify a tooltip for a given character index in the string.
     pub fn tooltip(
         mut self,
@@ -582,6 +600,36 @@ impl Element for InteractiveText {
             global_id.unwrap(),
             |interactive_state, cx| {
                 let mut interactive_state = interactive_state.unwrap_or_default();
+                if let Some(range_hover_listener) = self.range_hover_listener.take() {
+                    let hitbox = hitbox.clone();
+                    let text_layout = text_layout.clone();
+                    let clickable_ranges = self.clickable_ranges.clone();
+                    let hovered_range_ix = interactive_state.hovered_range_ix.clone();
+                    cx.on_mouse_event(move |event: &MouseMoveEvent, phase, cx| {
+                        if phase != DispatchPhase::Bubble {
+                            return;
+                        }
+
+                        let updated = if hitbox.is_hovered(cx) {
+                            text_layout
+                                .index_for_position(event.position)
+                                .ok()
+                                .and_then(|ix| {
+                                    clickable_ranges
+                                        .iter()
+                                        .position(|range| range.contains(&ix))
+                                })
+                        } else {
+                            None
+                        };
+                        if hovered_range_ix.get() != updated {
+                            hovered_range_ix.set(updated);
+                            range_hover_listener(updated, cx);
+                            cx.refresh();
+                        }
+                    });
+                }
+
                 if let Some(click_listener) = self.click_listener.take() {
                     let mouse_position = cx.mouse_position();
                     if let Some(ix) = text_layout.index_for_position(mouse_position).ok() {
@@ -258,6 +258,40 @@ impl Deref for ScrollWheelEvent {
     }
 }
 
+/// A trackpad pinch gesture from the platform.
+/// Only emitted on platforms with native pinch recognition.
+#[derive(Clone, Debug, Default)]
+pub struct PinchGestureEvent {
+    /// The position of the gesture's centroid on the window.
+    pub position: Point<Pixels>,
+
+    /// The change in magnification for this event, where 0.0 is no change. Multiply the
+    /// current zoom level by `1.0 + delta_scale` to apply it.
+    pub delta_scale: f32,
+
+    /// The modifiers that were held down during the gesture.
+    pub modifiers: Modifiers,
+
+    /// The phase of the gesture. Cancelled gestures are reported as [`TouchPhase::Ended`].
+    pub phase: TouchPhase,
+}
+
+impl Sealed for PinchGestureEvent {}
+impl InputEvent for PinchGestureEvent {
+    fn to_platform_input(self) -> PlatformInput {
+        PlatformInput::PinchGesture(self)
+    }
+}
+impl MouseEvent for PinchGestureEvent {}
+
+impl Deref for PinchGestureEvent {
+    type Target = Modifiers;
+
+    fn deref(&self) -> &Self::Target {
+        &self.modifiers
+    }
+}
+
 /// The scroll delta for a scroll wheel event.
 #[derive(Clone, Copy, Debug)]
 pub enum ScrollDelta {
@@ -402,6 +436,8 @@ pub enum PlatformInput {
     MouseExited(MouseExitEvent),
     /// The scroll wheel was used.
     ScrollWheel(ScrollWheelEvent),
+    /// A pinch gesture was performed on the trackpad.
+    PinchGesture(PinchGestureEvent),
     /// Files were dragged and dropped onto the window.
     FileDrop(FileDropEvent),
 }
@@ -417,6 +453,7 @@ impl PlatformInput {
             PlatformInput::MouseMove(event) => Some(event),
             PlatformInput::MouseExited(event) => Some(event),
             PlatformInput::ScrollWheel(event) => Some(event),
+            PlatformInput::PinchGesture(event) => Some(event),
             PlatformInput::FileDrop(event) => Some(event),
         }
     }
@@ -431,6 +468,7 @@ impl PlatformInput {
             PlatformInput::MouseMove(_) => None,
             PlatformInput::MouseExited(_) => None,
             PlatformInput::ScrollWheel(_) => None,
+            PlatformInput::PinchGesture(_) => None,
             PlatformInput::FileDrop(_) => None,
         }
     }
@@ -24,10 +24,10 @@ mod windows;
 
 use crate::{
     point, Action, AnyWindowHandle, AsyncWindowContext, BackgroundExecutor, Bounds, DevicePixels,
-    DispatchEventResult, Font, FontId, FontMetrics, FontRun, ForegroundExecutor, GlyphId, Keymap,
-    LineLayout, Pixels, PlatformInput, Point, RenderGlyphParams, RenderImageParams,
-    RenderSvgParams, Scene, SharedString, Size, Task, TaskLabel, WindowContext,
-    DEFAULT_WINDOW_SIZE,
+    DispatchEventResult, Font, FontId, FontMetrics, FontRun, FontStyle, FontWeight,
+    ForegroundExecutor, GlyphId, Keymap, LineLayout, OutlineSegment, Pixels, PlatformInput, Point,
+    RenderGlyphParams, RenderImageParams, RenderSvgParams, Scene, SharedString, Size, Task,
+    TaskLabel, WindowContext, DEFAULT_WINDOW_SIZE,
 };
 use anyhow::Result;
 use async_task::Runnable;
@@ -291,9 +291,11 @@ pub(crate) trait PlatformTextSystem: Send + Sync {
     fn all_font_families(&self) -> Vec<String>;
     fn font_id(&self, descriptor: &Font) -> Result<FontId>;
     fn font_metrics(&self, font_id: FontId) -> FontMetrics;
+    fn face_weight_and_style(&self, font_id: FontId) -> (FontWeight, FontStyle);
     fn typographic_bounds(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Bounds<f32>>;
     fn advance(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Size<f32>>;
     fn glyph_for_char(&self, font_id: FontId, ch: char) -> Option<GlyphId>;
+    fn glyph_outline(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Vec<OutlineSegment>>;
     fn glyph_raster_bounds(&self, params: &RenderGlyphParams) -> Result<Bounds<DevicePixels>>;
     fn rasterize_glyph(
         &self,
@@ -350,6 +352,14 @@ pub(crate) trait PlatformAtlas: Send + Sync {
         key: &AtlasKey,
         build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
     ) -> Result<Option<AtlasTile>>;
+
+    /// Like [`Self::get_or_insert_with`] for each of the keys, but locking the atlas once for
+    /// all of them. `build` is given the index of the key whose tile is missing.
+    fn get_or_insert_many<'a>(
+        &self,
+        keys: &[AtlasKey],
+        build: &mut dyn FnMut(usize) -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Vec<Option<AtlasTile>>>;
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -377,7 +387,7 @@ pub(crate) enum AtlasTextureKind {
     Path = 2,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(C)]
 pub(crate) struct TileId(pub(crate) u32);
 
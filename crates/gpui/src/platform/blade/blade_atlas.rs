@@ -116,23 +116,42 @@ impl PlatformAtlas for BladeAtlas {
         key: &AtlasKey,
         build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
     ) -> Result<Option<AtlasTile>> {
+        self.0.lock().get_or_insert_with(key, build)
+    }
+
+    fn get_or_insert_many<'a>(
+        &self,
+        keys: &[AtlasKey],
+        build: &mut dyn FnMut(usize) -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Vec<Option<AtlasTile>>> {
         let mut lock = self.0.lock();
-        if let Some(tile) = lock.tiles_by_key.get(key) {
+        keys.iter()
+            .enumerate()
+            .map(|(ix, key)| lock.get_or_insert_with(key, &mut || build(ix)))
+            .collect()
+    }
+}
+
+impl BladeAtlasState {
+    fn get_or_insert_with<'a>(
+        &mut self,
+        key: &AtlasKey,
+        build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Option<AtlasTile>> {
+        if let Some(tile) = self.tiles_by_key.get(key) {
             Ok(Some(tile.clone()))
         } else {
             profiling::scope!("new tile");
             let Some((size, bytes)) = build()? else {
                 return Ok(None);
             };
-            let tile = lock.allocate(size, key.texture_kind());
-            lock.upload_texture(tile.texture_id, tile.bounds, &bytes);
-            lock.tiles_by_key.insert(key.clone(), tile.clone());
+            let tile = self.allocate(size, key.texture_kind());
+            self.upload_texture(tile.texture_id, tile.bounds, &bytes);
+            self.tiles_by_key.insert(key.clone(), tile.clone());
             Ok(Some(tile))
         }
     }
-}
 
-impl BladeAtlasState {
     fn allocate(&mut self, size: Size<DevicePixels>, texture_kind: AtlasTextureKind) -> AtlasTile {
         let textures = &mut self.storage[texture_kind];
         textures
@@ -1,13 +1,13 @@
 use crate::{
     point, size, Bounds, DevicePixels, Font, FontFeatures, FontId, FontMetrics, FontRun, FontStyle,
-    FontWeight, GlyphId, LineLayout, Pixels, PlatformTextSystem, Point, RenderGlyphParams,
-    ShapedGlyph, SharedString, Size,
+    FontWeight, GenericFontFamily, GlyphId, LineLayout, OutlineSegment, Pixels, PlatformTextSystem,
+    Point, RenderGlyphParams, ShapedGlyph, SharedString, Size, TextRenderingMode,
 };
 use anyhow::{anyhow, Context, Ok, Result};
 use collections::HashMap;
 use cosmic_text::{
-    Attrs, AttrsList, CacheKey, Family, Font as CosmicTextFont, FontSystem, ShapeBuffer, ShapeLine,
-    SwashCache,
+    Attrs, AttrsList, CacheKey, Command, Family, Font as CosmicTextFont, FontSystem, ShapeBuffer,
+    ShapeLine, SwashCache,
 };
 
 use itertools::Itertools;
@@ -137,6 +137,20 @@ impl PlatformTextSystem for CosmicTextSystem {
         }
     }
 
+    fn face_weight_and_style(&self, font_id: FontId) -> (FontWeight, FontStyle) {
+        let lock = self.0.read();
+        let font = &lock.loaded_fonts_store[font_id.0];
+        lock.font_system
+            .db()
+            .face(font.id())
+            .map_or_else(Default::default, |face_info| {
+                (
+                    FontWeight(face_info.weight.0.into()),
+                    face_info.style.into(),
+                )
+            })
+    }
+
     fn typographic_bounds(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Bounds<f32>> {
         let lock = self.0.read();
         let glyph_metrics = lock.loaded_fonts_store[font_id.0]
@@ -162,6 +176,10 @@ impl PlatformTextSystem for CosmicTextSystem {
         self.0.read().glyph_for_char(font_id, ch)
     }
 
+    fn glyph_outline(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Vec<OutlineSegment>> {
+        self.0.write().glyph_outline(font_id, glyph_id)
+    }
+
     fn glyph_raster_bounds(&self, params: &RenderGlyphParams) -> Result<Bounds<DevicePixels>> {
         self.0.write().raster_bounds(params)
     }
@@ -205,9 +223,14 @@ impl CosmicTextSystemState {
     ) -> Result<SmallVec<[FontId; 4]>> {
         // TODO: Determine the proper system UI font.
         let name = if name == ".SystemUIFont" {
-            "Zed Plex Sans"
+            "Zed Plex Sans".to_string()
+        } else if let Some(generic) = GenericFontFamily::from_name(name) {
+            self.font_system
+                .db()
+                .family_name(&generic.into())
+                .to_string()
         } else {
-            name
+            name.to_string()
         };
 
         let mut font_ids = SmallVec::new();
@@ -278,6 +301,43 @@ impl CosmicTextSystemState {
             .map_or(false, |postscript_name| postscript_name == "NotoColorEmoji")
     }
 
+    fn glyph_outline(&mut self, font_id: FontId, glyph_id: GlyphId) -> Result<Vec<OutlineSegment>> {
+        let font = &self.loaded_fonts_store[font_id.0];
+        // Scale the outline to the size of the em square, so that it's in font units.
+        let units_per_em = font.as_swash().metrics(&[]).units_per_em;
+        let commands = self
+            .swash_cache
+            .get_outline_commands(
+                &mut self.font_system,
+                CacheKey::new(
+                    font.id(),
+                    glyph_id.0 as u16,
+                    units_per_em as f32,
+                    (0.0, 0.0),
+                    cosmic_text::CacheKeyFlags::empty(),
+                )
+                .0,
+            )
+            .with_context(|| format!("no outline for {glyph_id:?} in font {font:?}"))?;
+        Ok(commands
+            .iter()
+            .map(|command| match command {
+                Command::MoveTo(to) => OutlineSegment::MoveTo(point(to.x, to.y)),
+                Command::LineTo(to) => OutlineSegment::LineTo(point(to.x, to.y)),
+                Command::QuadTo(ctrl, to) => OutlineSegment::QuadTo {
+                    ctrl: point(ctrl.x, ctrl.y),
+                    to: point(to.x, to.y),
+                },
+                Command::CurveTo(ctrl1, ctrl2, to) => OutlineSegment::CubicTo {
+                    ctrl1: point(ctrl1.x, ctrl1.y),
+                    ctrl2: point(ctrl2.x, ctrl2.y),
+                    to: point(to.x, to.y),
+                },
+                Command::Close => OutlineSegment::Close,
+            })
+            .collect())
+    }
+
     fn raster_bounds(&mut self, params: &RenderGlyphParams) -> Result<Bounds<DevicePixels>> {
         let font = &self.loaded_fonts_store[params.font_id.0];
         let font_system = &mut self.font_system;
@@ -336,6 +396,11 @@ impl CosmicTextSystemState {
                 for pixel in image.data.chunks_exact_mut(4) {
                     pixel.swap(0, 2);
                 }
+            } else if params.rendering_mode == TextRenderingMode::Aliased {
+                // Swash always antialiases, so threshold its coverage mask instead.
+                for coverage in image.data.iter_mut() {
+                    *coverage = if *coverage >= 128 { 255 } else { 0 };
+                }
             }
 
             Ok((bitmap_size, image.data))
@@ -491,12 +556,24 @@ impl From<FontWeight> for cosmic_text::Weight {
     }
 }
 
+impl From<GenericFontFamily> for Family<'static> {
+    fn from(generic: GenericFontFamily) -> Self {
+        match generic {
+            GenericFontFamily::Serif => Family::Serif,
+            GenericFontFamily::SansSerif => Family::SansSerif,
+            GenericFontFamily::Monospace => Family::Monospace,
+            GenericFontFamily::Cursive => Family::Cursive,
+            GenericFontFamily::Fantasy => Family::Fantasy,
+        }
+    }
+}
+
 impl From<FontStyle> for cosmic_text::Style {
     fn from(style: FontStyle) -> Self {
         match style {
             FontStyle::Normal => cosmic_text::Style::Normal,
             FontStyle::Italic => cosmic_text::Style::Italic,
-            FontStyle::Oblique => cosmic_text::Style::Oblique,
+            FontStyle::Oblique(_) => cosmic_text::Style::Oblique,
         }
     }
 }
@@ -506,10 +583,20 @@ fn font_into_properties(font: &crate::Font) -> font_kit::properties::Properties
         style: match font.style {
             crate::FontStyle::Normal => font_kit::properties::Style::Normal,
             crate::FontStyle::Italic => font_kit::properties::Style::Italic,
-            crate::FontStyle::Oblique => font_kit::properties::Style::Oblique,
+            crate::FontStyle::Oblique(_) => font_kit::properties::Style::Oblique,
         },
         weight: font_kit::properties::Weight(font.weight.0),
-        stretch: Default::default(),
+        stretch: font_kit::properties::Stretch(font.width.0 / 100.0),
+    }
+}
+
+impl From<cosmic_text::Style> for FontStyle {
+    fn from(style: cosmic_text::Style) -> Self {
+        match style {
+            cosmic_text::Style::Normal => FontStyle::Normal,
+            cosmic_text::Style::Italic => FontStyle::Italic,
+            cosmic_text::Style::Oblique => FontStyle::Oblique(None),
+        }
     }
 }
 
@@ -62,25 +62,44 @@ impl PlatformAtlas for MetalAtlas {
         key: &AtlasKey,
         build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
     ) -> Result<Option<AtlasTile>> {
+        self.0.lock().get_or_insert_with(key, build)
+    }
+
+    fn get_or_insert_many<'a>(
+        &self,
+        keys: &[AtlasKey],
+        build: &mut dyn FnMut(usize) -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Vec<Option<AtlasTile>>> {
         let mut lock = self.0.lock();
-        if let Some(tile) = lock.tiles_by_key.get(key) {
+        keys.iter()
+            .enumerate()
+            .map(|(ix, key)| lock.get_or_insert_with(key, &mut || build(ix)))
+            .collect()
+    }
+}
+
+impl MetalAtlasState {
+    fn get_or_insert_with<'a>(
+        &mut self,
+        key: &AtlasKey,
+        build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Option<AtlasTile>> {
+        if let Some(tile) = self.tiles_by_key.get(key) {
             Ok(Some(tile.clone()))
         } else {
             let Some((size, bytes)) = build()? else {
                 return Ok(None);
             };
-            let tile = lock
+            let tile = self
                 .allocate(size, key.texture_kind())
                 .ok_or_else(|| anyhow!("failed to allocate"))?;
-            let texture = lock.texture(tile.texture_id);
+            let texture = self.texture(tile.texture_id);
             texture.upload(tile.bounds, &bytes);
-            lock.tiles_by_key.insert(key.clone(), tile.clone());
+            self.tiles_by_key.insert(key.clone(), tile.clone());
             Ok(Some(tile))
         }
     }
-}
 
-impl MetalAtlasState {
     fn allocate(
         &mut self,
         size: Size<DevicePixels>,
@@ -1,7 +1,8 @@
 use crate::{
     point, px, size, Bounds, DevicePixels, Font, FontFeatures, FontId, FontMetrics, FontRun,
-    FontStyle, FontWeight, GlyphId, LineLayout, Pixels, PlatformTextSystem, Point,
-    RenderGlyphParams, Result, ShapedGlyph, ShapedRun, SharedString, Size, SUBPIXEL_VARIANTS,
+    FontStyle, FontWeight, FontWidth, GenericFontFamily, GlyphId, LineLayout, OutlineSegment,
+    Pixels, PlatformTextSystem, Point, RenderGlyphParams, Result, ShapedGlyph, ShapedRun,
+    SharedString, Size, TextRenderingMode, SUBPIXEL_VARIANTS,
 };
 use anyhow::anyhow;
 use cocoa::appkit::{CGFloat, CGPoint};
@@ -26,16 +27,19 @@ use core_text::{
     string_attributes::kCTFontAttributeName,
 };
 use font_kit::{
+    family_name::FamilyName,
     font::Font as FontKitFont,
     handle::Handle,
     hinting::HintingOptions,
     metrics::Metrics,
-    properties::{Style as FontkitStyle, Weight as FontkitWeight},
-    source::SystemSource,
+    outline::OutlineSink,
+    properties::{Stretch as FontkitStretch, Style as FontkitStyle, Weight as FontkitWeight},
+    source::{Source, SystemSource},
     sources::mem::MemSource,
 };
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
 use pathfinder_geometry::{
+    line_segment::LineSegment2F,
     rect::{RectF, RectI},
     transform2d::Transform2F,
     vector::{Vector2F, Vector2I},
@@ -142,7 +146,7 @@ impl PlatformTextSystem for MacTextSystem {
                 &font_kit::properties::Properties {
                     style: font.style.into(),
                     weight: font.weight.into(),
-                    stretch: Default::default(),
+                    stretch: font.width.into(),
                 },
             )?;
 
@@ -156,6 +160,11 @@ impl PlatformTextSystem for MacTextSystem {
         self.0.read().fonts[font_id.0].metrics().into()
     }
 
+    fn face_weight_and_style(&self, font_id: FontId) -> (FontWeight, FontStyle) {
+        let properties = self.0.read().fonts[font_id.0].properties();
+        (FontWeight(properties.weight.0), properties.style.into())
+    }
+
     fn typographic_bounds(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Bounds<f32>> {
         Ok(self.0.read().fonts[font_id.0]
             .typographic_bounds(glyph_id.0)?
@@ -170,6 +179,12 @@ impl PlatformTextSystem for MacTextSystem {
         self.0.read().glyph_for_char(font_id, ch)
     }
 
+    fn glyph_outline(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Vec<OutlineSegment>> {
+        let mut outline = OutlineSegments::default();
+        self.0.read().fonts[font_id.0].outline(glyph_id.0, HintingOptions::None, &mut outline)?;
+        Ok(outline.0)
+    }
+
     fn glyph_raster_bounds(&self, params: &RenderGlyphParams) -> Result<Bounds<DevicePixels>> {
         self.0.read().raster_bounds(params)
     }
@@ -220,10 +235,14 @@ impl MacTextSystemState {
         };
 
         let mut font_ids = SmallVec::new();
-        let family = self
-            .memory_source
-            .select_family_by_name(name)
-            .or_else(|_| self.system_source.select_family_by_name(name))?;
+        let family = if let Some(generic) = GenericFontFamily::from_name(name) {
+            self.system_source
+                .select_family_by_generic_name(&generic.into())?
+        } else {
+            self.memory_source
+                .select_family_by_name(name)
+                .or_else(|_| self.system_source.select_family_by_name(name))?
+        };
         for font in family.fonts() {
             let mut font = font.load()?;
 
@@ -340,7 +359,12 @@ impl MacTextSystemState {
                 params.font_size.into(),
                 scale,
                 HintingOptions::None,
-                font_kit::canvas::RasterizationOptions::GrayscaleAa,
+                match params.rendering_mode {
+                    TextRenderingMode::Subpixel | TextRenderingMode::Grayscale => {
+                        font_kit::canvas::RasterizationOptions::GrayscaleAa
+                    }
+                    TextRenderingMode::Aliased => font_kit::canvas::RasterizationOptions::Bilevel,
+                },
             )?
             .into())
     }
@@ -403,8 +427,12 @@ impl MacTextSystemState {
             let subpixel_shift = params
                 .subpixel_variant
                 .map(|v| v as f32 / SUBPIXEL_VARIANTS as f32);
-            cx.set_allows_font_subpixel_positioning(true);
-            cx.set_should_subpixel_position_fonts(true);
+            let subpixel_positioning = params.rendering_mode == TextRenderingMode::Subpixel;
+            cx.set_allows_font_subpixel_positioning(subpixel_positioning);
+            cx.set_should_subpixel_position_fonts(subpixel_positioning);
+            if params.rendering_mode == TextRenderingMode::Aliased {
+                cx.set_should_antialias(false);
+            }
             cx.set_allows_font_subpixel_quantization(false);
             cx.set_should_subpixel_quantize_fonts(false);
             self.fonts[params.font_id.0]
@@ -557,6 +585,39 @@ impl<'a> StringIndexConverter<'a> {
     }
 }
 
+/// Collects the outline font-kit reports for a glyph.
+#[derive(Default)]
+struct OutlineSegments(Vec<OutlineSegment>);
+
+impl OutlineSink for OutlineSegments {
+    fn move_to(&mut self, to: Vector2F) {
+        self.0.push(OutlineSegment::MoveTo(to.into()));
+    }
+
+    fn line_to(&mut self, to: Vector2F) {
+        self.0.push(OutlineSegment::LineTo(to.into()));
+    }
+
+    fn quadratic_curve_to(&mut self, ctrl: Vector2F, to: Vector2F) {
+        self.0.push(OutlineSegment::QuadTo {
+            ctrl: ctrl.into(),
+            to: to.into(),
+        });
+    }
+
+    fn cubic_curve_to(&mut self, ctrl: LineSegment2F, to: Vector2F) {
+        self.0.push(OutlineSegment::CubicTo {
+            ctrl1: ctrl.from().into(),
+            ctrl2: ctrl.to().into(),
+            to: to.into(),
+        });
+    }
+
+    fn close(&mut self) {
+        self.0.push(OutlineSegment::Close);
+    }
+}
+
 impl From<Metrics> for FontMetrics {
     fn from(metrics: Metrics) -> Self {
         FontMetrics {
@@ -618,18 +679,52 @@ impl From<Vector2F> for Size<f32> {
     }
 }
 
+impl From<Vector2F> for Point<f32> {
+    fn from(vec: Vector2F) -> Self {
+        point(vec.x(), vec.y())
+    }
+}
+
 impl From<FontWeight> for FontkitWeight {
     fn from(value: FontWeight) -> Self {
         FontkitWeight(value.0)
     }
 }
 
+impl From<GenericFontFamily> for FamilyName {
+    fn from(generic: GenericFontFamily) -> Self {
+        match generic {
+            GenericFontFamily::Serif => FamilyName::Serif,
+            GenericFontFamily::SansSerif => FamilyName::SansSerif,
+            GenericFontFamily::Monospace => FamilyName::Monospace,
+            GenericFontFamily::Cursive => FamilyName::Cursive,
+            GenericFontFamily::Fantasy => FamilyName::Fantasy,
+        }
+    }
+}
+
+impl From<FontWidth> for FontkitStretch {
+    fn from(value: FontWidth) -> Self {
+        FontkitStretch(value.0 / 100.0)
+    }
+}
+
 impl From<FontStyle> for FontkitStyle {
     fn from(style: FontStyle) -> Self {
         match style {
             FontStyle::Normal => FontkitStyle::Normal,
             FontStyle::Italic => FontkitStyle::Italic,
-            FontStyle::Oblique => FontkitStyle::Oblique,
+            FontStyle::Oblique(_) => FontkitStyle::Oblique,
+        }
+    }
+}
+
+impl From<FontkitStyle> for FontStyle {
+    fn from(style: FontkitStyle) -> Self {
+        match style {
+            FontkitStyle::Normal => FontStyle::Normal,
+            FontkitStyle::Italic => FontStyle::Italic,
+            FontkitStyle::Oblique => FontStyle::Oblique(None),
         }
     }
 }
@@ -0,0 +1,481 @@
+use crate::{
+    Bounds, ContentMask, Corners, DevicePixels, Hsla, MonochromeSprite, Path, PolychromeSprite,
+    PrimitiveBatch, Quad, Rgba, ScaledPixels, Scene, Shadow, Size, TestAtlas, Underline,
+};
+use image::RgbaImage;
+use std::{f32::consts::PI, ops::Range};
+
+/// The weights used to convert a color to grayscale, as in the shaders.
+const GRAYSCALE_FACTORS: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Renders scenes on the CPU, so that tests can compare frames against reference images, see
+/// [`crate::VisualTestContext::render_to_image`]. Each primitive is shaded as the blade shaders
+/// shade it, but the output isn't pixel-exact with the GPU renderers. Surfaces aren't rendered
+/// and sprite transformations are ignored.
+pub(crate) struct TestRenderer<'a> {
+    atlas: &'a TestAtlas,
+    width: usize,
+    height: usize,
+    /// Premultiplied colors, row by row.
+    pixels: Vec<[f32; 4]>,
+}
+
+impl<'a> TestRenderer<'a> {
+    /// Renders the scene into an image of the given size, over a transparent background.
+    pub fn render(scene: &Scene, atlas: &'a TestAtlas, size: Size<DevicePixels>) -> RgbaImage {
+        let width = size.width.0.max(0) as usize;
+        let height = size.height.0.max(0) as usize;
+        let mut renderer = Self {
+            atlas,
+            width,
+            height,
+            pixels: vec![[0.; 4]; width * height],
+        };
+
+        for batch in scene.batches() {
+            match batch {
+                PrimitiveBatch::Shadows(shadows) => shadows
+                    .iter()
+                    .for_each(|shadow| renderer.draw_shadow(shadow)),
+                PrimitiveBatch::Quads(quads) => {
+                    quads.iter().for_each(|quad| renderer.draw_quad(quad))
+                }
+                PrimitiveBatch::Paths(paths) => {
+                    paths.iter().for_each(|path| renderer.draw_path(path))
+                }
+                PrimitiveBatch::Underlines(underlines) => underlines
+                    .iter()
+                    .for_each(|underline| renderer.draw_underline(underline)),
+                PrimitiveBatch::MonochromeSprites { sprites, .. } => sprites
+                    .iter()
+                    .for_each(|sprite| renderer.draw_monochrome_sprite(sprite)),
+                PrimitiveBatch::PolychromeSprites { sprites, .. } => sprites
+                    .iter()
+                    .for_each(|sprite| renderer.draw_polychrome_sprite(sprite)),
+                PrimitiveBatch::Surfaces(_) => {}
+            }
+        }
+
+        renderer.into_image()
+    }
+
+    fn draw_shadow(&mut self, shadow: &Shadow) {
+        let color = rgba(shadow.color);
+        let blur_radius = shadow.blur_radius.0;
+        let margin = 3. * blur_radius;
+        let bounds = Bounds {
+            origin: shadow
+                .bounds
+                .origin
+                .map(|value| value - ScaledPixels(margin)),
+            size: shadow
+                .bounds
+                .size
+                .map(|value| value + ScaledPixels(2. * margin)),
+        };
+        let (half_size, center) = half_size_and_center(&shadow.bounds);
+        self.fill(&bounds, &shadow.content_mask, |x, y| {
+            let center_to_point = [x - center[0], y - center[1]];
+            let corner_radius = pick_corner_radius(center_to_point, &shadow.corner_radii);
+            if blur_radius == 0. {
+                let distance = rounded_rect_distance(center_to_point, half_size, corner_radius);
+                return Some(with_alpha(color, saturate(0.5 - distance)));
+            }
+
+            // Integrate the blur along y with a few samples, as the shaders do.
+            let low = center_to_point[1] - half_size[1];
+            let high = center_to_point[1] + half_size[1];
+            let start = (-3. * blur_radius).clamp(low, high);
+            let end = (3. * blur_radius).clamp(low, high);
+            let step = (end - start) / 4.;
+            let mut alpha = 0.;
+            let mut sample_y = start + step * 0.5;
+            for _ in 0..4 {
+                let blur = blur_along_x(
+                    center_to_point[0],
+                    center_to_point[1] - sample_y,
+                    blur_radius,
+                    corner_radius,
+                    half_size,
+                );
+                alpha += blur * gaussian(sample_y, blur_radius) * step;
+                sample_y += step;
+            }
+            Some(with_alpha(color, alpha))
+        });
+    }
+
+    fn draw_quad(&mut self, quad: &Quad) {
+        let background = rgba(quad.background);
+        let border_color = rgba(quad.border_color);
+        let (half_size, center) = half_size_and_center(&quad.bounds);
+        let widths = &quad.border_widths;
+        self.fill(&quad.bounds, &quad.content_mask, |x, y| {
+            let center_to_point = [x - center[0], y - center[1]];
+            let corner_radius = pick_corner_radius(center_to_point, &quad.corner_radii);
+            let distance = rounded_rect_distance(center_to_point, half_size, corner_radius);
+
+            let vertical_border = if center_to_point[0] > 0. {
+                widths.right.0
+            } else {
+                widths.left.0
+            };
+            let horizontal_border = if center_to_point[1] > 0. {
+                widths.bottom.0
+            } else {
+                widths.top.0
+            };
+            let inset_size = [
+                half_size[0] - corner_radius - vertical_border,
+                half_size[1] - corner_radius - horizontal_border,
+            ];
+            let point_to_inset_corner = [
+                center_to_point[0].abs() - inset_size[0],
+                center_to_point[1].abs() - inset_size[1],
+            ];
+            let border_width = if point_to_inset_corner[0] < 0. && point_to_inset_corner[1] < 0. {
+                0.
+            } else if point_to_inset_corner[1] > point_to_inset_corner[0] {
+                horizontal_border
+            } else {
+                vertical_border
+            };
+
+            let mut color = background;
+            if border_width > 0. {
+                // Blend the border over the background, fading into the background as the
+                // point moves inside the border.
+                let blended_border = over(background, border_color);
+                let t = saturate(0.5 - (distance + border_width));
+                color = mix(blended_border, background, t);
+            }
+            Some(with_alpha(color, saturate(0.5 - distance)))
+        });
+    }
+
+    /// Paths are filled with the even-odd rule, from 2x2 samples per pixel.
+    fn draw_path(&mut self, path: &Path<ScaledPixels>) {
+        const SAMPLE_OFFSETS: [[f32; 2]; 4] =
+            [[-0.25, -0.25], [0.25, -0.25], [-0.25, 0.25], [0.25, 0.25]];
+
+        let color = rgba(path.color);
+        let triangles = path.vertices.chunks_exact(3).collect::<Vec<_>>();
+        self.fill(&path.bounds, &path.content_mask, |x, y| {
+            let covered_samples = SAMPLE_OFFSETS
+                .iter()
+                .filter(|offset| {
+                    let sample = [x + offset[0], y + offset[1]];
+                    let crossings = triangles
+                        .iter()
+                        .filter(|triangle| {
+                            let positions = [0, 1, 2].map(|ix| {
+                                let position = triangle[ix].xy_position;
+                                [position.x.0, position.y.0]
+                            });
+                            let Some(weights) = barycentric(sample, positions) else {
+                                return false;
+                            };
+                            // Curve triangles only cover the points inside the curve.
+                            let s = (0..3)
+                                .map(|ix| weights[ix] * triangle[ix].st_position.x)
+                                .sum::<f32>();
+                            let t = (0..3)
+                                .map(|ix| weights[ix] * triangle[ix].st_position.y)
+                                .sum::<f32>();
+                            s * s - t <= 0.
+                        })
+                        .count();
+                    crossings % 2 == 1
+                })
+                .count();
+            Some(with_alpha(
+                color,
+                covered_samples as f32 / SAMPLE_OFFSETS.len() as f32,
+            ))
+        });
+    }
+
+    fn draw_underline(&mut self, underline: &Underline) {
+        let color = rgba(underline.color);
+        let bounds = &underline.bounds;
+        let thickness = underline.thickness.0;
+        self.fill(bounds, &underline.content_mask, |x, y| {
+            if !underline.wavy {
+                return Some(color);
+            }
+
+            let height = bounds.size.height.0;
+            let st = [
+                (x - bounds.origin.x.0) / height,
+                (y - bounds.origin.y.0) / height - 0.5,
+            ];
+            let frequency = PI * 3. * thickness / 8.;
+            let amplitude = 1. / (2. * thickness);
+            let sine = (st[0] * frequency).sin() * amplitude;
+            let sine_slope = (st[0] * frequency).cos() * amplitude * frequency;
+            let distance = (st[1] - sine) / (1. + sine_slope * sine_slope).sqrt() * height;
+            let half_thickness = thickness * 0.5;
+            let alpha =
+                saturate(0.5 - (-(distance + half_thickness)).max(distance - half_thickness));
+            Some(with_alpha(color, alpha))
+        });
+    }
+
+    fn draw_monochrome_sprite(&mut self, sprite: &MonochromeSprite) {
+        let Some(tile) = self.atlas.tile_contents(sprite.tile.tile_id) else {
+            return;
+        };
+        let color = rgba(sprite.color);
+        let tile_size = sprite.tile.bounds.size;
+        self.fill(&sprite.bounds, &sprite.content_mask, |x, y| {
+            let ix = tile_index(&sprite.bounds, tile_size, x, y)?;
+            Some(with_alpha(color, f32::from(*tile.get(ix)?) / 255.))
+        });
+    }
+
+    fn draw_polychrome_sprite(&mut self, sprite: &PolychromeSprite) {
+        let Some(tile) = self.atlas.tile_contents(sprite.tile.tile_id) else {
+            return;
+        };
+        let tile_size = sprite.tile.bounds.size;
+        let (half_size, center) = half_size_and_center(&sprite.bounds);
+        self.fill(&sprite.bounds, &sprite.content_mask, |x, y| {
+            let ix = tile_index(&sprite.bounds, tile_size, x, y)? * 4;
+            // Polychrome tiles are stored as BGRA.
+            let [b, g, r, a] = [0, 1, 2, 3].map(|channel| {
+                tile.get(ix + channel)
+                    .map_or(0., |value| f32::from(*value) / 255.)
+            });
+            let mut color = [r, g, b, a];
+            if sprite.grayscale {
+                let gray = (0..3).map(|ix| color[ix] * GRAYSCALE_FACTORS[ix]).sum();
+                color = [gray, gray, gray, a];
+            }
+
+            let center_to_point = [x - center[0], y - center[1]];
+            let corner_radius = pick_corner_radius(center_to_point, &sprite.corner_radii);
+            let distance = rounded_rect_distance(center_to_point, half_size, corner_radius);
+            Some(with_alpha(color, sprite.opacity * saturate(0.5 - distance)))
+        });
+    }
+
+    /// Blends the color returned by `shade` for each pixel center in the bounds and the content
+    /// mask over the image. `shade` is given the pixel center and returns a straight color, or
+    /// `None` to leave the pixel alone.
+    fn fill(
+        &mut self,
+        bounds: &Bounds<ScaledPixels>,
+        content_mask: &ContentMask<ScaledPixels>,
+        mut shade: impl FnMut(f32, f32) -> Option<[f32; 4]>,
+    ) {
+        let mask = &content_mask.bounds;
+        let left = bounds.origin.x.0.max(mask.origin.x.0);
+        let top = bounds.origin.y.0.max(mask.origin.y.0);
+        let right = (bounds.origin.x + bounds.size.width)
+            .0
+            .min((mask.origin.x + mask.size.width).0);
+        let bottom = (bounds.origin.y + bounds.size.height)
+            .0
+            .min((mask.origin.y + mask.size.height).0);
+
+        for y in pixel_range(top, bottom, self.height) {
+            for x in pixel_range(left, right, self.width) {
+                let Some([r, g, b, a]) = shade(x as f32 + 0.5, y as f32 + 0.5) else {
+                    continue;
+                };
+                let a = saturate(a);
+                let pixel = &mut self.pixels[y * self.width + x];
+                *pixel = [
+                    r * a + pixel[0] * (1. - a),
+                    g * a + pixel[1] * (1. - a),
+                    b * a + pixel[2] * (1. - a),
+                    a + pixel[3] * (1. - a),
+                ];
+            }
+        }
+    }
+
+    fn into_image(self) -> RgbaImage {
+        let mut image = RgbaImage::new(self.width as u32, self.height as u32);
+        for (target, [r, g, b, a]) in image.pixels_mut().zip(self.pixels) {
+            let unpremultiply = |channel: f32| if a > 0. { channel / a } else { 0. };
+            target.0 = [unpremultiply(r), unpremultiply(g), unpremultiply(b), a]
+                .map(|channel| (saturate(channel) * 255.).round() as u8);
+        }
+        image
+    }
+}
+
+/// The pixels whose centers lie between `start` and `end`, limited to `len` pixels.
+fn pixel_range(start: f32, end: f32, len: usize) -> Range<usize> {
+    let start = (start - 0.5).ceil().clamp(0., len as f32) as usize;
+    let end = (end - 0.5).ceil().clamp(0., len as f32) as usize;
+    start..end.max(start)
+}
+
+/// The index of the tile pixel under the given point of a sprite.
+fn tile_index(
+    bounds: &Bounds<ScaledPixels>,
+    tile_size: Size<DevicePixels>,
+    x: f32,
+    y: f32,
+) -> Option<usize> {
+    let tile_x = ((x - bounds.origin.x.0) / bounds.size.width.0 * tile_size.width.0 as f32) as i32;
+    let tile_y =
+        ((y - bounds.origin.y.0) / bounds.size.height.0 * tile_size.height.0 as f32) as i32;
+    if tile_x < 0 || tile_y < 0 || tile_x >= tile_size.width.0 || tile_y >= tile_size.height.0 {
+        return None;
+    }
+    Some((tile_y * tile_size.width.0 + tile_x) as usize)
+}
+
+fn half_size_and_center(bounds: &Bounds<ScaledPixels>) -> ([f32; 2], [f32; 2]) {
+    let half_size = [bounds.size.width.0 / 2., bounds.size.height.0 / 2.];
+    let center = [
+        bounds.origin.x.0 + half_size[0],
+        bounds.origin.y.0 + half_size[1],
+    ];
+    (half_size, center)
+}
+
+fn pick_corner_radius(center_to_point: [f32; 2], radii: &Corners<ScaledPixels>) -> f32 {
+    match (center_to_point[0] < 0., center_to_point[1] < 0.) {
+        (true, true) => radii.top_left.0,
+        (true, false) => radii.bottom_left.0,
+        (false, true) => radii.top_right.0,
+        (false, false) => radii.bottom_right.0,
+    }
+}
+
+/// The signed distance from a point to the edge of a rounded rectangle, given relative to its
+/// center.
+fn rounded_rect_distance(
+    center_to_point: [f32; 2],
+    half_size: [f32; 2],
+    corner_radius: f32,
+) -> f32 {
+    let edge_to_point = [
+        center_to_point[0].abs() - half_size[0] + corner_radius,
+        center_to_point[1].abs() - half_size[1] + corner_radius,
+    ];
+    let outside = [edge_to_point[0].max(0.), edge_to_point[1].max(0.)];
+    (outside[0] * outside[0] + outside[1] * outside[1]).sqrt()
+        + edge_to_point[0].max(edge_to_point[1]).min(0.)
+        - corner_radius
+}
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    (-(x * x) / (2. * sigma * sigma)).exp() / ((2. * PI).sqrt() * sigma)
+}
+
+/// An approximation of the error function, as used by the shaders.
+fn erf(value: f32) -> f32 {
+    let sign = value.signum();
+    let a = value.abs();
+    let r1 = 1. + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    let r2 = r1 * r1;
+    sign - sign / (r2 * r2)
+}
+
+fn blur_along_x(x: f32, y: f32, sigma: f32, corner: f32, half_size: [f32; 2]) -> f32 {
+    let delta = (half_size[1] - corner - y.abs()).min(0.);
+    let curved = half_size[0] - corner + (corner * corner - delta * delta).max(0.).sqrt();
+    let integral = |edge: f32| 0.5 + 0.5 * erf((x + edge) * (0.5f32.sqrt() / sigma));
+    integral(curved) - integral(-curved)
+}
+
+/// The barycentric weights of a point in a triangle, if it lies inside it.
+fn barycentric(point: [f32; 2], triangle: [[f32; 2]; 3]) -> Option<[f32; 3]> {
+    let [a, b, c] = triangle;
+    let area = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+    if area == 0. {
+        return None;
+    }
+    let weight_b = ((point[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (point[1] - a[1])) / area;
+    let weight_c = ((b[0] - a[0]) * (point[1] - a[1]) - (point[0] - a[0]) * (b[1] - a[1])) / area;
+    let weight_a = 1. - weight_b - weight_c;
+    (weight_a >= 0. && weight_b >= 0. && weight_c >= 0.).then_some([weight_a, weight_b, weight_c])
+}
+
+fn rgba(color: Hsla) -> [f32; 4] {
+    let color = Rgba::from(color);
+    [color.r, color.g, color.b, color.a]
+}
+
+fn with_alpha(color: [f32; 4], alpha: f32) -> [f32; 4] {
+    [color[0], color[1], color[2], color[3] * alpha]
+}
+
+/// Blends `above` over `below`, with straight alpha.
+fn over(below: [f32; 4], above: [f32; 4]) -> [f32; 4] {
+    let alpha = above[3] + below[3] * (1. - above[3]);
+    if alpha == 0. {
+        return [0.; 4];
+    }
+    let channel =
+        |ix: usize| (above[ix] * above[3] + below[ix] * below[3] * (1. - above[3])) / alpha;
+    [channel(0), channel(1), channel(2), alpha]
+}
+
+fn mix(from: [f32; 4], to: [f32; 4], t: f32) -> [f32; 4] {
+    [0, 1, 2, 3].map(|ix| from[ix] + (to[ix] - from[ix]) * t)
+}
+
+fn saturate(value: f32) -> f32 {
+    value.clamp(0., 1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point, px, red, size, Edges, Pixels};
+
+    fn quad(bounds: Bounds<Pixels>, corner_radius: f32, border_width: f32) -> Quad {
+        Quad {
+            bounds: bounds.scale(1.),
+            content_mask: ContentMask {
+                bounds: Bounds::new(point(px(0.), px(0.)), size(px(100.), px(100.))).scale(1.),
+            },
+            background: red(),
+            border_color: Hsla::default(),
+            corner_radii: Corners::all(ScaledPixels(corner_radius)),
+            border_widths: Edges::all(ScaledPixels(border_width)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_quads() {
+        let mut scene = Scene::default();
+        scene.insert_primitive(quad(
+            Bounds::new(point(px(1.), px(1.)), size(px(2.), px(2.))),
+            0.,
+            0.,
+        ));
+        scene.insert_primitive(quad(
+            Bounds::new(point(px(4.), px(0.)), size(px(4.), px(4.))),
+            2.,
+            0.,
+        ));
+        scene.finish();
+
+        let atlas = TestAtlas::new();
+        let image = TestRenderer::render(&scene, &atlas, size(DevicePixels(8), DevicePixels(4)));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(1, 1).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(2, 2).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(3, 3).0, [0, 0, 0, 0]);
+
+        // The corners of the rounded quad are cut off, but its middle is filled.
+        assert!(image.get_pixel(4, 0).0[3] < 128);
+        assert_eq!(image.get_pixel(5, 1).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_pixel_range() {
+        assert_eq!(pixel_range(0., 2., 10), 0..2);
+        assert_eq!(pixel_range(0.6, 2.4, 10), 1..2);
+        assert_eq!(pixel_range(-5., 20., 10), 0..10);
+        assert_eq!(pixel_range(3., 1., 10), 3..3);
+    }
+}
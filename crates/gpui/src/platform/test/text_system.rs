@@ -0,0 +1,252 @@
+use crate::{
+    point, px, size, Bounds, DevicePixels, Font, FontId, FontMetrics, FontRun, FontStyle,
+    FontWeight, GlyphId, LineLayout, OutlineSegment, Pixels, PlatformTextSystem, RenderGlyphParams,
+    ShapedGlyph, ShapedRun, Size, UnicodeScript,
+};
+use anyhow::{anyhow, Result};
+use smallvec::SmallVec;
+use std::borrow::Cow;
+
+/// The number of font units in the test font's em square.
+const UNITS_PER_EM: f32 = 1000.;
+const ASCENT: f32 = 800.;
+const DESCENT: f32 = -200.;
+const CAP_HEIGHT: f32 = 700.;
+/// The gap between a glyph's box and the edges of its advance.
+const SIDE_BEARING: f32 = 50.;
+/// The color of emoji boxes, in the BGRA order of polychrome rasters. It's orange, so that
+/// images show whether emoji were tinted with the text color.
+const EMOJI_BGRA: [u8; 4] = [0x00, 0x88, 0xff, 0xff];
+
+/// A platform text system with a single built-in font, whose metrics don't depend on the fonts
+/// installed on the machine, so that tests can assert exact pixel values.
+///
+/// Every font resolves to the same regular face, so bold and italic text is synthesized. Each
+/// character is its own glyph, whose id is its code point, and there is no kerning or ligature
+/// substitution. Characters advance by half an em, or a whole em for CJK characters and emoji,
+/// and default-ignorable characters such as soft hyphens take up no space. Visible glyphs are
+/// drawn as boxes up to the cap height, which are orange for emoji.
+pub(crate) struct TestTextSystem;
+
+impl TestTextSystem {
+    /// The family name of the test font. Any other family name resolves to it too.
+    pub const FONT_FAMILY: &'static str = "Zed Test";
+
+    /// The advance of the given character, in font units.
+    fn advance_for_char(ch: char) -> f32 {
+        match ch {
+            '\u{ad}' | '\u{200b}'..='\u{200d}' | '\u{fe00}'..='\u{fe0f}' => 0.,
+            _ => match UnicodeScript::of(ch) {
+                Some(
+                    UnicodeScript::Han
+                    | UnicodeScript::Hangul
+                    | UnicodeScript::Hiragana
+                    | UnicodeScript::Katakana
+                    | UnicodeScript::Emoji,
+                ) => UNITS_PER_EM,
+                _ => UNITS_PER_EM / 2.,
+            },
+        }
+    }
+
+    /// The character for the given glyph, and whether it is drawn as a box.
+    fn char_for_glyph(glyph_id: GlyphId) -> Result<(char, bool)> {
+        let ch = char::from_u32(glyph_id.0).ok_or_else(|| anyhow!("no glyph {glyph_id:?}"))?;
+        let is_visible = !ch.is_whitespace() && Self::advance_for_char(ch) > 0.;
+        Ok((ch, is_visible))
+    }
+}
+
+impl PlatformTextSystem for TestTextSystem {
+    fn add_fonts(&self, _fonts: Vec<Cow<'static, [u8]>>) -> Result<()> {
+        Ok(())
+    }
+
+    fn all_font_names(&self) -> Vec<String> {
+        vec![Self::FONT_FAMILY.to_string()]
+    }
+
+    fn all_font_families(&self) -> Vec<String> {
+        vec![Self::FONT_FAMILY.to_string()]
+    }
+
+    fn font_id(&self, _descriptor: &Font) -> Result<FontId> {
+        Ok(FontId(0))
+    }
+
+    fn font_metrics(&self, _font_id: FontId) -> FontMetrics {
+        FontMetrics {
+            units_per_em: UNITS_PER_EM as u32,
+            ascent: ASCENT,
+            descent: DESCENT,
+            line_gap: 0.,
+            underline_position: -100.,
+            underline_thickness: 50.,
+            cap_height: CAP_HEIGHT,
+            x_height: 500.,
+            bounding_box: Bounds {
+                origin: point(0., DESCENT),
+                size: size(UNITS_PER_EM, ASCENT - DESCENT),
+            },
+        }
+    }
+
+    fn face_weight_and_style(&self, _font_id: FontId) -> (FontWeight, FontStyle) {
+        (FontWeight::NORMAL, FontStyle::Normal)
+    }
+
+    fn typographic_bounds(&self, _font_id: FontId, glyph_id: GlyphId) -> Result<Bounds<f32>> {
+        let (ch, _) = Self::char_for_glyph(glyph_id)?;
+        Ok(Bounds {
+            origin: point(0., DESCENT),
+            size: size(Self::advance_for_char(ch), ASCENT - DESCENT),
+        })
+    }
+
+    fn advance(&self, _font_id: FontId, glyph_id: GlyphId) -> Result<Size<f32>> {
+        let (ch, _) = Self::char_for_glyph(glyph_id)?;
+        Ok(size(Self::advance_for_char(ch), 0.))
+    }
+
+    fn glyph_for_char(&self, _font_id: FontId, ch: char) -> Option<GlyphId> {
+        Some(GlyphId(ch as u32))
+    }
+
+    fn glyph_outline(&self, _font_id: FontId, glyph_id: GlyphId) -> Result<Vec<OutlineSegment>> {
+        let (ch, is_visible) = Self::char_for_glyph(glyph_id)?;
+        if !is_visible {
+            return Ok(Vec::new());
+        }
+
+        let left = SIDE_BEARING;
+        let right = Self::advance_for_char(ch) - SIDE_BEARING;
+        Ok(vec![
+            OutlineSegment::MoveTo(point(left, 0.)),
+            OutlineSegment::LineTo(point(right, 0.)),
+            OutlineSegment::LineTo(point(right, CAP_HEIGHT)),
+            OutlineSegment::LineTo(point(left, CAP_HEIGHT)),
+            OutlineSegment::Close,
+        ])
+    }
+
+    fn glyph_raster_bounds(&self, params: &RenderGlyphParams) -> Result<Bounds<DevicePixels>> {
+        let (ch, is_visible) = Self::char_for_glyph(params.glyph_id)?;
+        if !is_visible {
+            return Ok(Bounds::default());
+        }
+
+        let device_pixels = |units: f32| {
+            DevicePixels(
+                (units * params.font_size.0 * params.scale_factor / UNITS_PER_EM).round() as i32,
+            )
+        };
+        let left = device_pixels(SIDE_BEARING);
+        let right = device_pixels(Self::advance_for_char(ch) - SIDE_BEARING);
+        let top = device_pixels(CAP_HEIGHT);
+        Ok(Bounds {
+            origin: point(left, DevicePixels(-top.0)),
+            size: size(right - left, top),
+        })
+    }
+
+    fn rasterize_glyph(
+        &self,
+        params: &RenderGlyphParams,
+        raster_bounds: Bounds<DevicePixels>,
+    ) -> Result<(Size<DevicePixels>, Vec<u8>)> {
+        if raster_bounds.size.width.0 == 0 || raster_bounds.size.height.0 == 0 {
+            return Err(anyhow!("glyph bounds are empty"));
+        }
+
+        let pixels = raster_bounds.size.width.0 as usize * raster_bounds.size.height.0 as usize;
+        let raster = if params.is_emoji {
+            EMOJI_BGRA.repeat(pixels)
+        } else {
+            vec![255; pixels]
+        };
+        Ok((raster_bounds.size, raster))
+    }
+
+    fn layout_line(&self, text: &str, font_size: Pixels, runs: &[FontRun]) -> LineLayout {
+        let mut shaped_runs = Vec::with_capacity(runs.len());
+        let mut run_start = 0;
+        let mut x = px(0.);
+        for run in runs {
+            let run_end = (run_start + run.len).min(text.len());
+            let mut glyphs = SmallVec::new();
+            for (offset, ch) in text[run_start..run_end].char_indices() {
+                glyphs.push(ShapedGlyph {
+                    id: GlyphId(ch as u32),
+                    position: point(x, px(0.)),
+                    index: run_start + offset,
+                    is_emoji: UnicodeScript::of(ch) == Some(UnicodeScript::Emoji),
+                });
+                x += to_pixels(Self::advance_for_char(ch), font_size);
+            }
+            shaped_runs.push(ShapedRun {
+                font_id: run.font_id,
+                glyphs,
+            });
+            run_start = run_end;
+        }
+
+        LineLayout {
+            font_size,
+            width: x,
+            ascent: to_pixels(ASCENT, font_size),
+            descent: to_pixels(-DESCENT, font_size),
+            runs: shaped_runs,
+            len: text.len(),
+        }
+    }
+}
+
+/// Converts font units to pixels, dividing last so that round numbers stay exact.
+fn to_pixels(units: f32, font_size: Pixels) -> Pixels {
+    px(units * font_size.0 / UNITS_PER_EM)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_line_is_deterministic() {
+        let text_system = TestTextSystem;
+        let text = "ab日\u{ad}c";
+        let layout = text_system.layout_line(
+            text,
+            px(10.),
+            &[
+                FontRun {
+                    len: 2,
+                    font_id: FontId(0),
+                },
+                FontRun {
+                    len: text.len() - 2,
+                    font_id: FontId(1),
+                },
+            ],
+        );
+
+        assert_eq!(layout.width, px(25.));
+        assert_eq!((layout.ascent, layout.descent), (px(8.), px(2.)));
+        assert_eq!(layout.runs.len(), 2);
+        let positions = layout
+            .runs
+            .iter()
+            .flat_map(|run| run.glyphs.iter())
+            .map(|glyph| (glyph.index, glyph.position.x))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            [
+                (0, px(0.)),
+                (1, px(5.)),
+                (2, px(10.)),
+                (5, px(20.)),
+                (7, px(20.))
+            ]
+        );
+    }
+}
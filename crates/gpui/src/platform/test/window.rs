@@ -1,13 +1,16 @@
 use crate::{
-    AnyWindowHandle, AtlasKey, AtlasTextureId, AtlasTile, Bounds, DispatchEventResult, Pixels,
-    PlatformAtlas, PlatformDisplay, PlatformInput, PlatformInputHandler, PlatformWindow, Point,
-    Size, TestPlatform, TileId, WindowAppearance, WindowBackgroundAppearance, WindowBounds,
-    WindowParams,
+    AnyWindowHandle, AtlasKey, AtlasTextureId, AtlasTile, Bounds, DevicePixels,
+    DispatchEventResult, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
+    PlatformInputHandler, PlatformWindow, Point, Scene, Size, TestPlatform, TestRenderer, TileId,
+    WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowParams,
 };
+use anyhow::Result;
 use collections::HashMap;
+use image::RgbaImage;
 use parking_lot::Mutex;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 use std::{
+    borrow::Cow,
     rc::{Rc, Weak},
     sync::{self, Arc},
 };
@@ -19,7 +22,7 @@ pub(crate) struct TestWindowState {
     pub(crate) title: Option<String>,
     pub(crate) edited: bool,
     platform: Weak<TestPlatform>,
-    sprite_atlas: Arc<dyn PlatformAtlas>,
+    sprite_atlas: Arc<TestAtlas>,
     pub(crate) should_close_handler: Option<Box<dyn FnMut() -> bool>>,
     input_callback: Option<Box<dyn FnMut(PlatformInput) -> DispatchEventResult>>,
     active_status_change_callback: Option<Box<dyn FnMut(bool)>>,
@@ -85,6 +88,17 @@ impl TestWindow {
         self.0.lock().resize_callback = Some(callback);
     }
 
+    /// Renders the given scene at the window's size in device pixels, see [`TestRenderer`].
+    pub(crate) fn render_to_image(&self, scene: &Scene) -> RgbaImage {
+        let scale_factor = self.scale_factor();
+        let lock = self.0.lock();
+        let size = lock
+            .bounds
+            .size
+            .map(|value| DevicePixels::from((value.0 * scale_factor).round() as i32));
+        TestRenderer::render(scene, &lock.sprite_atlas, size)
+    }
+
     pub(crate) fn simulate_active_status_change(&self, active: bool) {
         let mut lock = self.0.lock();
         let Some(mut callback) = lock.active_status_change_callback.take() else {
@@ -274,6 +288,7 @@ impl PlatformWindow for TestWindow {
 pub(crate) struct TestAtlasState {
     next_id: u32,
     tiles: HashMap<AtlasKey, AtlasTile>,
+    contents: HashMap<TileId, Arc<[u8]>>,
 }
 
 pub(crate) struct TestAtlas(Mutex<TestAtlasState>);
@@ -283,50 +298,83 @@ impl TestAtlas {
         TestAtlas(Mutex::new(TestAtlasState {
             next_id: 0,
             tiles: HashMap::default(),
+            contents: HashMap::default(),
         }))
     }
+
+    /// The bytes that were built for the given tile, see [`TestRenderer`].
+    pub(crate) fn tile_contents(&self, tile_id: TileId) -> Option<Arc<[u8]>> {
+        self.0.lock().contents.get(&tile_id).cloned()
+    }
+
+    /// The number of tiles that have been built.
+    #[cfg(test)]
+    pub(crate) fn tile_count(&self) -> usize {
+        self.0.lock().tiles.len()
+    }
 }
 
 impl PlatformAtlas for TestAtlas {
     fn get_or_insert_with<'a>(
         &self,
-        key: &crate::AtlasKey,
-        build: &mut dyn FnMut() -> anyhow::Result<
-            Option<(Size<crate::DevicePixels>, std::borrow::Cow<'a, [u8]>)>,
-        >,
-    ) -> anyhow::Result<Option<crate::AtlasTile>> {
+        key: &AtlasKey,
+        build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Option<AtlasTile>> {
         let mut state = self.0.lock();
         if let Some(tile) = state.tiles.get(key) {
             return Ok(Some(tile.clone()));
         }
         drop(state);
 
-        let Some((size, _)) = build()? else {
+        let Some((size, bytes)) = build()? else {
             return Ok(None);
         };
 
+        Ok(Some(self.0.lock().insert(key, size, bytes)))
+    }
+
+    fn get_or_insert_many<'a>(
+        &self,
+        keys: &[AtlasKey],
+        build: &mut dyn FnMut(usize) -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Vec<Option<AtlasTile>>> {
         let mut state = self.0.lock();
-        state.next_id += 1;
-        let texture_id = state.next_id;
-        state.next_id += 1;
-        let tile_id = state.next_id;
-
-        state.tiles.insert(
-            key.clone(),
-            crate::AtlasTile {
-                texture_id: AtlasTextureId {
-                    index: texture_id,
-                    kind: crate::AtlasTextureKind::Path,
-                },
-                tile_id: TileId(tile_id),
-                padding: 0,
-                bounds: crate::Bounds {
-                    origin: Point::default(),
-                    size,
-                },
-            },
-        );
+        keys.iter()
+            .enumerate()
+            .map(|(ix, key)| {
+                if let Some(tile) = state.tiles.get(key) {
+                    return Ok(Some(tile.clone()));
+                }
+                let Some((size, bytes)) = build(ix)? else {
+                    return Ok(None);
+                };
+                Ok(Some(state.insert(key, size, bytes)))
+            })
+            .collect()
+    }
+}
 
-        Ok(Some(state.tiles[key].clone()))
+impl TestAtlasState {
+    fn insert(&mut self, key: &AtlasKey, size: Size<DevicePixels>, bytes: Cow<[u8]>) -> AtlasTile {
+        self.next_id += 1;
+        let texture_id = self.next_id;
+        self.next_id += 1;
+        let tile_id = self.next_id;
+
+        let tile = AtlasTile {
+            texture_id: AtlasTextureId {
+                index: texture_id,
+                kind: key.texture_kind(),
+            },
+            tile_id: TileId(tile_id),
+            padding: 0,
+            bounds: Bounds {
+                origin: Point::default(),
+                size,
+            },
+        };
+        self.tiles.insert(key.clone(), tile.clone());
+        self.contents.insert(TileId(tile_id), bytes.into());
+        tile
     }
 }
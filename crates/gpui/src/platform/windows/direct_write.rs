@@ -201,6 +201,12 @@ impl PlatformTextSystem for DirectWriteTextSystem {
         self.0.read().font_metrics(font_id)
     }
 
+    fn face_weight_and_style(&self, font_id: FontId) -> (FontWeight, FontStyle) {
+        let lock = self.0.read();
+        let font_face = &lock.fonts[font_id.0].font_face;
+        unsafe { (font_face.GetWeight().into(), font_face.GetStyle().into()) }
+    }
+
     fn typographic_bounds(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Bounds<f32>> {
         self.0.read().get_typographic_bounds(font_id, glyph_id)
     }
@@ -213,6 +219,15 @@ impl PlatformTextSystem for DirectWriteTextSystem {
         self.0.read().glyph_for_char(font_id, ch)
     }
 
+    fn glyph_outline(
+        &self,
+        _font_id: FontId,
+        _glyph_id: GlyphId,
+    ) -> anyhow::Result<Vec<OutlineSegment>> {
+        // todo(windows): Collect the outline from `IDWriteFontFace::GetGlyphRunOutline`
+        Err(anyhow!("glyph outlines are not supported on Windows yet"))
+    }
+
     fn glyph_raster_bounds(
         &self,
         params: &RenderGlyphParams,
@@ -295,6 +310,7 @@ impl DirectWriteState {
         family_name: &str,
         font_weight: FontWeight,
         font_style: FontStyle,
+        font_width: FontWidth,
         font_features: &FontFeatures,
         is_system_font: bool,
     ) -> Option<FontId> {
@@ -308,7 +324,7 @@ impl DirectWriteState {
             .GetMatchingFonts(
                 &HSTRING::from(family_name),
                 font_weight.into(),
-                DWRITE_FONT_STRETCH_NORMAL,
+                font_width.into(),
                 font_style.into(),
             )
             .log_err()?;
@@ -364,14 +380,18 @@ impl DirectWriteState {
                     family.as_ref(),
                     target_font.weight,
                     target_font.style,
+                    target_font.width,
                     &target_font.features,
                 )
                 .unwrap()
             } else {
+                let family_name = GenericFontFamily::from_name(&target_font.family)
+                    .map_or(target_font.family.as_ref(), generic_family_name);
                 self.find_font_id(
-                    target_font.family.as_ref(),
+                    family_name,
                     target_font.weight,
                     target_font.style,
+                    target_font.width,
                     &target_font.features,
                 )
                 .unwrap_or_else(|| {
@@ -381,6 +401,7 @@ impl DirectWriteState {
                         family.as_ref(),
                         target_font.weight,
                         target_font.style,
+                        target_font.width,
                         &target_font.features,
                         true,
                     )
@@ -395,16 +416,31 @@ impl DirectWriteState {
         family_name: &str,
         weight: FontWeight,
         style: FontStyle,
+        width: FontWidth,
         features: &FontFeatures,
     ) -> Option<FontId> {
         // try to find target font in custom font collection first
-        self.get_font_id_from_font_collection(family_name, weight, style, features, false)
+        self.get_font_id_from_font_collection(family_name, weight, style, width, features, false)
             .or_else(|| {
-                self.get_font_id_from_font_collection(family_name, weight, style, features, true)
+                self.get_font_id_from_font_collection(
+                    family_name,
+                    weight,
+                    style,
+                    width,
+                    features,
+                    true,
+                )
             })
             .or_else(|| {
                 self.update_system_font_collection();
-                self.get_font_id_from_font_collection(family_name, weight, style, features, true)
+                self.get_font_id_from_font_collection(
+                    family_name,
+                    weight,
+                    style,
+                    width,
+                    features,
+                    true,
+                )
             })
     }
 
@@ -439,7 +475,7 @@ impl DirectWriteState {
                     collection,
                     font_info.font_face.GetWeight(),
                     font_info.font_face.GetStyle(),
-                    DWRITE_FONT_STRETCH_NORMAL,
+                    font_info.font_face.GetStretch(),
                     font_size.0,
                     &HSTRING::from(&self.components.locale),
                 )?;
@@ -711,6 +747,9 @@ impl DirectWriteState {
                 bitmap_dpi * params.scale_factor,
             );
             render_target.SetTextRenderingParams(&self.components.render_context.params);
+            if params.rendering_mode == TextRenderingMode::Aliased {
+                render_target.SetTextAntialiasMode(D2D1_TEXT_ANTIALIAS_MODE_ALIASED);
+            }
             render_target.BeginDraw();
 
             if params.is_emoji {
@@ -1092,7 +1131,7 @@ impl Into<DWRITE_FONT_STYLE> for FontStyle {
         match self {
             FontStyle::Normal => DWRITE_FONT_STYLE_NORMAL,
             FontStyle::Italic => DWRITE_FONT_STYLE_ITALIC,
-            FontStyle::Oblique => DWRITE_FONT_STYLE_OBLIQUE,
+            FontStyle::Oblique(_) => DWRITE_FONT_STYLE_OBLIQUE,
         }
     }
 }
@@ -1102,7 +1141,7 @@ impl From<DWRITE_FONT_STYLE> for FontStyle {
         match value.0 {
             0 => FontStyle::Normal,
             1 => FontStyle::Italic,
-            2 => FontStyle::Oblique,
+            2 => FontStyle::Oblique(None),
             _ => unreachable!(),
         }
     }
@@ -1120,6 +1159,49 @@ impl From<DWRITE_FONT_WEIGHT> for FontWeight {
     }
 }
 
+// DirectWrite only has the nine named widths, indexed from 1.
+const DIRECT_WRITE_FONT_WIDTHS: [FontWidth; 9] = [
+    FontWidth::ULTRA_CONDENSED,
+    FontWidth::EXTRA_CONDENSED,
+    FontWidth::CONDENSED,
+    FontWidth::SEMI_CONDENSED,
+    FontWidth::NORMAL,
+    FontWidth::SEMI_EXPANDED,
+    FontWidth::EXPANDED,
+    FontWidth::EXTRA_EXPANDED,
+    FontWidth::ULTRA_EXPANDED,
+];
+
+impl Into<DWRITE_FONT_STRETCH> for FontWidth {
+    fn into(self) -> DWRITE_FONT_STRETCH {
+        let ix = DIRECT_WRITE_FONT_WIDTHS
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (a.0 - self.0).abs().total_cmp(&(b.0 - self.0).abs()))
+            .map_or(4, |(ix, _)| ix);
+        DWRITE_FONT_STRETCH(ix as i32 + 1)
+    }
+}
+
+impl From<DWRITE_FONT_STRETCH> for FontWidth {
+    fn from(value: DWRITE_FONT_STRETCH) -> Self {
+        DIRECT_WRITE_FONT_WIDTHS
+            .get((value.0 as usize).wrapping_sub(1))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+fn generic_family_name(generic: GenericFontFamily) -> &'static str {
+    match generic {
+        GenericFontFamily::Serif => "Times New Roman",
+        GenericFontFamily::SansSerif => "Arial",
+        GenericFontFamily::Monospace => "Consolas",
+        GenericFontFamily::Cursive => "Comic Sans MS",
+        GenericFontFamily::Fantasy => "Impact",
+    }
+}
+
 fn get_font_names_from_collection(
     collection: &IDWriteFontCollection1,
     locale: &str,
@@ -1153,6 +1235,7 @@ fn get_font_identifier_and_font_struct(
     let family_name = get_name(localized_family_name, locale).log_err()?;
     let weight = unsafe { font_face.GetWeight() };
     let style = unsafe { font_face.GetStyle() };
+    let width = unsafe { font_face.GetStretch() };
     let identifier = FontIdentifier {
         postscript_name,
         weight: weight.0,
@@ -1163,6 +1246,7 @@ fn get_font_identifier_and_font_struct(
         features: FontFeatures::default(),
         weight: weight.into(),
         style: style.into(),
+        width: width.into(),
     };
     let is_emoji = unsafe { font_face.IsColorFont().as_bool() };
     Some((identifier, font_struct, is_emoji))
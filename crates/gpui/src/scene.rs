@@ -110,6 +110,19 @@ impl Scene {
             .push(PaintOperation::Primitive(primitive));
     }
 
+    /// Inserts each of the sprites as [`Self::insert_primitive`] would, after reserving room for
+    /// all of them, e.g. for a run of glyphs.
+    pub fn insert_monochrome_sprites(
+        &mut self,
+        sprites: impl ExactSizeIterator<Item = MonochromeSprite>,
+    ) {
+        self.monochrome_sprites.reserve(sprites.len());
+        self.paint_operations.reserve(sprites.len());
+        for sprite in sprites {
+            self.insert_primitive(sprite);
+        }
+    }
+
     pub fn replay(&mut self, range: Range<usize>, prev_scene: &Scene) {
         for operation in &prev_scene.paint_operations[range] {
             match operation {
@@ -640,17 +653,21 @@ impl From<MonochromeSprite> for Primitive {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[repr(C)]
 pub(crate) struct PolychromeSprite {
     pub order: DrawOrder,
     pub grayscale: bool,
+    pub opacity: f32,
+    pub pad: u32, // align to 8 bytes
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
     pub corner_radii: Corners<ScaledPixels>,
     pub tile: AtlasTile,
 }
 
+impl Eq for PolychromeSprite {}
+
 impl Ord for PolychromeSprite {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match self.order.cmp(&other.order) {
@@ -754,6 +771,14 @@ impl Path<Pixels> {
         }
     }
 
+    /// Start a new contour at the given point, without drawing a line to it. Contours are filled
+    /// with the even-odd rule, so one inside another cuts a hole in it.
+    pub fn move_to(&mut self, to: Point<Pixels>) {
+        self.start = to;
+        self.current = to;
+        self.contour_count = 0;
+    }
+
     /// Draw a straight line from the current point to the given point.
     pub fn line_to(&mut self, to: Point<Pixels>) {
         self.contour_count += 1;
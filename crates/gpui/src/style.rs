@@ -7,8 +7,8 @@ use std::{
 use crate::{
     black, phi, point, quad, rems, AbsoluteLength, Bounds, ContentMask, Corners, CornersRefinement,
     CursorStyle, DefiniteLength, Edges, EdgesRefinement, Font, FontFeatures, FontStyle, FontWeight,
-    Hsla, Length, Pixels, Point, PointRefinement, Rgba, SharedString, Size, SizeRefinement, Styled,
-    TextRun, WindowContext,
+    FontWidth, Hsla, Length, Pixels, Point, PointRefinement, Rgba, SharedString, Size,
+    SizeRefinement, Styled, TextRun, WindowContext,
 };
 use collections::HashSet;
 use refineable::Refineable;
@@ -192,6 +192,9 @@ pub struct TextStyle {
     /// The font style, e.g. italic
     pub font_style: FontStyle,
 
+    /// The font width, e.g. condensed
+    pub font_width: FontWidth,
+
     /// The background color of the text
     pub background_color: Option<Hsla>,
 
@@ -220,6 +223,7 @@ impl Default for TextStyle {
             line_height: phi(),
             font_weight: FontWeight::default(),
             font_style: FontStyle::default(),
+            font_width: FontWidth::default(),
             background_color: None,
             underline: None,
             strikethrough: None,
@@ -269,6 +273,7 @@ impl TextStyle {
             features: self.font_features.clone(),
             weight: self.font_weight,
             style: self.font_style,
+            width: self.font_width,
         }
     }
 
@@ -281,12 +286,7 @@ impl TextStyle {
     pub fn to_run(&self, len: usize) -> TextRun {
         TextRun {
             len,
-            font: Font {
-                family: self.font_family.clone(),
-                features: Default::default(),
-                weight: self.font_weight,
-                style: self.font_style,
-            },
+            font: self.font(),
             color: self.color,
             background_color: self.background_color,
             underline: self.underline,
@@ -570,7 +570,8 @@ impl Default for Style {
 #[derive(Refineable, Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
 #[refineable(Debug)]
 pub struct UnderlineStyle {
-    /// The thickness of the underline.
+    /// The thickness of the underline. When painting text, zero means the font's suggested
+    /// thickness, see [`crate::FontMetrics::underline_thickness`].
     pub thickness: Pixels,
 
     /// The color of the underline.
@@ -584,7 +585,8 @@ pub struct UnderlineStyle {
 #[derive(Refineable, Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
 #[refineable(Debug)]
 pub struct StrikethroughStyle {
-    /// The thickness of the strikethrough.
+    /// The thickness of the strikethrough. When painting text, zero means the font's suggested
+    /// underline thickness, see [`crate::FontMetrics::underline_thickness`].
     pub thickness: Pixels,
 
     /// The color of the strikethrough.
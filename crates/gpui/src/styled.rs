@@ -1,6 +1,6 @@
 use crate::{
     self as gpui, hsla, point, px, relative, rems, AbsoluteLength, AlignItems, CursorStyle,
-    DefiniteLength, Fill, FlexDirection, FlexWrap, Font, FontStyle, FontWeight, Hsla,
+    DefiniteLength, Fill, FlexDirection, FlexWrap, Font, FontStyle, FontWeight, FontWidth, Hsla,
     JustifyContent, Length, Position, SharedString, StyleRefinement, Visibility, WhiteSpace,
 };
 use crate::{BoxShadow, TextStyleRefinement};
@@ -617,6 +617,14 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Set the font width of this element, this value cascades to its child elements.
+    fn font_width(mut self, width: FontWidth) -> Self {
+        self.text_style()
+            .get_or_insert_with(Default::default)
+            .font_width = Some(width);
+        self
+    }
+
     /// Set the background color of this element, this value cascades to its child elements.
     fn text_bg(mut self, bg: impl Into<Hsla>) -> Self {
         self.text_style()
@@ -800,6 +808,7 @@ pub trait Styled: Sized {
             features,
             weight,
             style,
+            width,
         } = font;
 
         let text_style = self.text_style().get_or_insert_with(Default::default);
@@ -807,6 +816,7 @@ pub trait Styled: Sized {
         text_style.font_features = Some(features);
         text_style.font_weight = Some(weight);
         text_style.font_style = Some(style);
+        text_style.font_width = Some(width);
 
         self
     }
@@ -1,18 +1,22 @@
 mod font_features;
+mod font_synthesis;
 mod line;
 mod line_layout;
 mod line_wrapper;
+mod script;
 
 pub use font_features::*;
+pub use font_synthesis::*;
 pub use line::*;
 pub use line_layout::*;
 pub use line_wrapper::*;
 use schemars::JsonSchema;
+pub use script::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    px, Bounds, DevicePixels, Hsla, Pixels, PlatformTextSystem, Point, Result, SharedString, Size,
-    StrikethroughStyle, UnderlineStyle,
+    point, px, Bounds, DevicePixels, Hsla, IsZero, Path, Pixels, PlatformAtlas, PlatformTextSystem,
+    Point, Result, SharedString, Size, StrikethroughStyle, UnderlineStyle,
 };
 use anyhow::anyhow;
 use collections::{BTreeSet, FxHashMap};
@@ -41,14 +45,30 @@ pub struct FontFamilyId(pub usize);
 
 pub(crate) const SUBPIXEL_VARIANTS: u8 = 4;
 
+/// How many glyph fallback choices are cached before the cache is cleared and refilled.
+const MAX_GLYPH_FALLBACKS: usize = 4096;
+
+/// The most glyph rasters a single call to [`TextSystem::prewarm_glyphs`] builds. Nothing is
+/// ever evicted from the glyph atlas, so this keeps one call from filling it.
+pub(crate) const MAX_PREWARMED_RASTERS: usize = 4096;
+
 /// The GPUI text rendering sub system.
 pub struct TextSystem {
     platform_text_system: Arc<dyn PlatformTextSystem>,
     font_ids_by_font: RwLock<FxHashMap<Font, Result<FontId>>>,
     font_metrics: RwLock<FxHashMap<FontId, FontMetrics>>,
+    monospace_fonts: RwLock<FxHashMap<FontId, bool>>,
+    face_styles: RwLock<FxHashMap<FontId, (FontWeight, FontStyle)>>,
     raster_bounds: RwLock<FxHashMap<RenderGlyphParams, Bounds<DevicePixels>>>,
     wrapper_pool: Mutex<FxHashMap<FontIdWithSize, Vec<LineWrapper>>>,
     font_runs_pool: Mutex<Vec<Vec<FontRun>>>,
+    line_layout_cache_budget: Arc<RwLock<LineLayoutCacheBudget>>,
+    shared_line_layouts: Arc<SharedLineLayouts>,
+    script_fallbacks: RwLock<FxHashMap<UnicodeScript, Vec<SharedString>>>,
+    default_fallbacks: RwLock<Vec<SharedString>>,
+    /// The fallback chosen for characters that a font has no glyph for, or `None` if the font's
+    /// own glyph is used. Cleared whenever the fallbacks or the available fonts change.
+    glyph_fallbacks: RwLock<FxHashMap<GlyphFallbackKey, Option<FontId>>>,
     fallback_font_stack: SmallVec<[Font; 2]>,
 }
 
@@ -57,10 +77,17 @@ impl TextSystem {
         TextSystem {
             platform_text_system,
             font_metrics: RwLock::default(),
+            monospace_fonts: RwLock::default(),
+            face_styles: RwLock::default(),
             raster_bounds: RwLock::default(),
             font_ids_by_font: RwLock::default(),
             wrapper_pool: Mutex::default(),
             font_runs_pool: Mutex::default(),
+            line_layout_cache_budget: Arc::default(),
+            shared_line_layouts: Arc::default(),
+            script_fallbacks: RwLock::default(),
+            default_fallbacks: RwLock::default(),
+            glyph_fallbacks: RwLock::default(),
             fallback_font_stack: smallvec![
                 // TODO: This is currently Zed-specific.
                 // We should allow GPUI users to provide their own fallback font stack.
@@ -90,9 +117,59 @@ impl TextSystem {
         names.into_iter().collect()
     }
 
+    /// Limit how many text layouts each window retains between frames.
+    /// By default, every layout used in the previous frame is retained.
+    pub fn set_line_layout_cache_budget(&self, budget: LineLayoutCacheBudget) {
+        *self.line_layout_cache_budget.write() = budget;
+    }
+
+    /// The limits on how many text layouts each window retains between frames.
+    pub fn line_layout_cache_budget(&self) -> LineLayoutCacheBudget {
+        *self.line_layout_cache_budget.read()
+    }
+
+    /// Set the font families, in order of preference, used for characters of the given script
+    /// that the requested font has no glyph for. Passing an empty list removes the fallbacks.
+    pub fn set_script_fallbacks(&self, script: UnicodeScript, families: Vec<SharedString>) {
+        let mut script_fallbacks = self.script_fallbacks.write();
+        if families.is_empty() {
+            script_fallbacks.remove(&script);
+        } else {
+            script_fallbacks.insert(script, families);
+        }
+        self.glyph_fallbacks.write().clear();
+    }
+
+    /// Use the given family for emoji that the requested font has no glyph for, instead of the
+    /// platform's default emoji font. If the family isn't installed, the platform default is used.
+    pub fn set_emoji_font(&self, family: SharedString) {
+        if let Err(error) = self.font_id(&font(family.clone())) {
+            log::error!("emoji font {family:?} not found, using the default instead: {error}");
+            self.set_script_fallbacks(UnicodeScript::Emoji, Vec::new());
+            return;
+        }
+        self.set_script_fallbacks(UnicodeScript::Emoji, vec![family]);
+    }
+
+    /// Set the font families, in order of preference, used for characters that neither the
+    /// requested font nor its script fallbacks have a glyph for. They are also tried before the
+    /// built-in fallback fonts when a requested font fails to load.
+    pub fn set_default_fallbacks(&self, families: Vec<SharedString>) {
+        *self.default_fallbacks.write() = families;
+        self.glyph_fallbacks.write().clear();
+    }
+
+    /// The font families used when a font has no glyph for a character, or fails to load.
+    pub fn default_fallbacks(&self) -> Vec<SharedString> {
+        self.default_fallbacks.read().clone()
+    }
+
     /// Add a font's data to the text system.
     pub fn add_fonts(&self, fonts: Vec<Cow<'static, [u8]>>) -> Result<()> {
-        self.platform_text_system.add_fonts(fonts)
+        self.platform_text_system.add_fonts(fonts)?;
+        // The new fonts may have glyphs that were previously missing.
+        self.glyph_fallbacks.write().clear();
+        Ok(())
     }
 
     /// Get the FontId for the configure font family and style.
@@ -141,6 +218,14 @@ impl TextSystem {
         if let Ok(font_id) = self.font_id(font) {
             return font_id;
         }
+        for family in self.default_fallbacks.read().iter() {
+            if let Ok(font_id) = self.font_id(&Font {
+                family: family.clone(),
+                ..font.clone()
+            }) {
+                return font_id;
+            }
+        }
         for fallback in &self.fallback_font_stack {
             if let Ok(font_id) = self.font_id(fallback) {
                 return font_id;
@@ -157,6 +242,33 @@ impl TextSystem {
         );
     }
 
+    /// Resolve the font as [`Self::resolve_font`] does, and report how closely the face that
+    /// text in it is shown with matches it, and which styles are synthesized. This lets font
+    /// settings warn that a family has no bold face, for example.
+    pub fn resolve_face_info(&self, font: &Font) -> FaceInfo {
+        let family_matched = self.font_id(font).is_ok();
+        let font_id = self.resolve_font(font);
+        let (face_weight, face_style) = self.face_weight_and_style(font_id);
+        // The font size only changes how strongly styles are synthesized, not whether they are.
+        let synthesis =
+            FontSynthesis::for_face(font.weight, font.style, face_weight, face_style, px(16.));
+        FaceInfo {
+            family_matched,
+            weight_matched: face_weight == font.weight,
+            synthetic_bold: synthesis.bold.is_some(),
+            synthetic_italic: synthesis.oblique.is_some(),
+        }
+    }
+
+    fn face_weight_and_style(&self, font_id: FontId) -> (FontWeight, FontStyle) {
+        let face_style = self.face_styles.read().get(&font_id).copied();
+        face_style.unwrap_or_else(|| {
+            let face_style = self.platform_text_system.face_weight_and_style(font_id);
+            self.face_styles.write().insert(font_id, face_style);
+            face_style
+        })
+    }
+
     /// Get the bounding box for the given font and font size.
     /// A font's bounding box is the smallest rectangle that could enclose all glyphs
     /// in the font. superimposed over one another.
@@ -195,6 +307,68 @@ impl TextSystem {
         Ok(result * font_size)
     }
 
+    /// Get the vector outline of the given glyph at the given font size, as a path whose origin
+    /// is the glyph's origin on the baseline. The path can be filled with
+    /// [`crate::WindowContext::paint_path`], or transformed for uses such as SVG export.
+    ///
+    /// Glyphs that have no outline, such as color bitmap glyphs, whitespace, and composite
+    /// glyphs the platform can't resolve, return an error rather than an empty path.
+    ///
+    /// Outlines aren't supported on Windows yet, where this always returns an error.
+    pub fn glyph_outline(
+        &self,
+        font_id: FontId,
+        glyph_id: GlyphId,
+        font_size: Pixels,
+    ) -> Result<Path<Pixels>> {
+        self.glyph_outline_at(font_id, glyph_id, font_size, Point::default())
+    }
+
+    pub(crate) fn glyph_outline_at(
+        &self,
+        font_id: FontId,
+        glyph_id: GlyphId,
+        font_size: Pixels,
+        origin: Point<Pixels>,
+    ) -> Result<Path<Pixels>> {
+        let outline = self.platform_text_system.glyph_outline(font_id, glyph_id)?;
+        let scale = font_size.0 / self.units_per_em(font_id) as f32;
+        path_for_outline(&outline, scale, origin)
+            .ok_or_else(|| anyhow!("glyph {glyph_id:?} in font {font_id:?} has no outline"))
+    }
+
+    /// Whether every printable ASCII character has the same advance in the given font.
+    pub fn is_monospace(&self, font_id: FontId) -> bool {
+        if let Some(is_monospace) = self.monospace_fonts.read().get(&font_id) {
+            return *is_monospace;
+        }
+
+        let mut advances = (' '..='~').map(|ch| {
+            let glyph_id = self.platform_text_system.glyph_for_char(font_id, ch)?;
+            let advance = self.platform_text_system.advance(font_id, glyph_id).ok()?;
+            Some(advance.width)
+        });
+        let first_advance = advances.next().flatten();
+        let is_monospace =
+            first_advance.is_some() && advances.all(|advance| advance == first_advance);
+        self.monospace_fonts.write().insert(font_id, is_monospace);
+        is_monospace
+    }
+
+    /// The styles that need to be faked to show text in the given weight and style with the given
+    /// font, because the font's face doesn't have them. For example, a family with no bold face
+    /// resolves bold text to its regular face, which is then emboldened.
+    pub fn font_synthesis(
+        &self,
+        font_id: FontId,
+        weight: FontWeight,
+        style: FontStyle,
+        font_size: Pixels,
+    ) -> FontSynthesis {
+        let (face_weight, face_style) = self.face_weight_and_style(font_id);
+        FontSynthesis::for_face(weight, style, face_weight, face_style, font_size)
+    }
+
     /// Get the number of font size units per 'em square',
     /// Per MDN: "an abstract square whose height is the intended distance between
     /// lines of type in the same type size"
@@ -212,6 +386,11 @@ impl TextSystem {
         self.read_metrics(font_id, |metrics| metrics.x_height(font_size))
     }
 
+    /// Get the suggested underline thickness for the given font and size.
+    pub fn underline_thickness(&self, font_id: FontId, font_size: Pixels) -> Pixels {
+        self.read_metrics(font_id, |metrics| metrics.underline_thickness(font_size))
+    }
+
     /// Get the recommended distance from the baseline for the given font
     pub fn ascent(&self, font_id: FontId, font_size: Pixels) -> Pixels {
         self.read_metrics(font_id, |metrics| metrics.ascent(font_size))
@@ -258,7 +437,15 @@ impl TextSystem {
             .entry(FontIdWithSize { font_id, font_size })
             .or_default();
         let wrapper = wrappers.pop().unwrap_or_else(|| {
-            LineWrapper::new(font_id, font_size, self.platform_text_system.clone())
+            let mut wrapper =
+                LineWrapper::new(font_id, font_size, self.platform_text_system.clone());
+            if self.is_monospace(font_id) {
+                wrapper.monospace_advance = self
+                    .advance(font_id, font_size, 'm')
+                    .ok()
+                    .map(|advance| advance.width);
+            }
+            wrapper
         });
 
         LineWrapperHandle {
@@ -267,6 +454,81 @@ impl TextSystem {
         }
     }
 
+    /// Append font runs covering `range` of `text`, which is styled with `font`, to `font_runs`.
+    /// Characters that `font` has no glyph for are given a script fallback or a default fallback,
+    /// if one is configured. The choice is cached per font, style and character, so that text
+    /// shaped every frame only looks glyphs up the first time.
+    fn push_font_runs(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        font: &Font,
+        font_id: FontId,
+        font_runs: &mut Vec<FontRun>,
+    ) {
+        let script_fallbacks = self.script_fallbacks.read();
+        let default_fallbacks = self.default_fallbacks.read();
+        let run_text = if script_fallbacks.is_empty() && default_fallbacks.is_empty() {
+            None
+        } else {
+            text.get(range.clone())
+        };
+        let Some(run_text) = run_text else {
+            push_font_run(font_runs, range.len(), font_id);
+            return;
+        };
+
+        for ch in run_text.chars() {
+            let script_families = UnicodeScript::of(ch)
+                .and_then(|script| script_fallbacks.get(&script))
+                .map_or(&[][..], |families| families.as_slice());
+            if script_families.is_empty() && default_fallbacks.is_empty() {
+                push_font_run(font_runs, ch.len_utf8(), font_id);
+                continue;
+            }
+
+            let key = GlyphFallbackKey {
+                font_id,
+                weight: font.weight,
+                style: font.style,
+                width: font.width,
+                ch,
+            };
+            let cached_fallback = self.glyph_fallbacks.read().get(&key).copied();
+            let fallback_id = cached_fallback.unwrap_or_else(|| {
+                let needs_fallback = self
+                    .platform_text_system
+                    .glyph_for_char(font_id, ch)
+                    .is_none();
+                let fallback_id = if needs_fallback {
+                    script_families
+                        .iter()
+                        .chain(default_fallbacks.iter())
+                        .find_map(|family| {
+                            let fallback_id = self
+                                .font_id(&Font {
+                                    family: family.clone(),
+                                    ..font.clone()
+                                })
+                                .ok()?;
+                            self.platform_text_system
+                                .glyph_for_char(fallback_id, ch)
+                                .map(|_| fallback_id)
+                        })
+                } else {
+                    None
+                };
+                let mut glyph_fallbacks = self.glyph_fallbacks.write();
+                if glyph_fallbacks.len() >= MAX_GLYPH_FALLBACKS {
+                    glyph_fallbacks.clear();
+                }
+                glyph_fallbacks.insert(key, fallback_id);
+                fallback_id
+            });
+            push_font_run(font_runs, ch.len_utf8(), fallback_id.unwrap_or(font_id));
+        }
+    }
+
     /// Get the rasterized size and location of a specific, rendered glyph.
     pub(crate) fn raster_bounds(&self, params: &RenderGlyphParams) -> Result<Bounds<DevicePixels>> {
         let raster_bounds = self.raster_bounds.upgradable_read();
@@ -274,19 +536,97 @@ impl TextSystem {
             Ok(*bounds)
         } else {
             let mut raster_bounds = RwLockUpgradableReadGuard::upgrade(raster_bounds);
-            let bounds = self.platform_text_system.glyph_raster_bounds(params)?;
+            let bounds = if params.is_synthesized() {
+                let unsynthesized_bounds = self
+                    .platform_text_system
+                    .glyph_raster_bounds(&params.without_synthesis())?;
+                synthesized_raster_bounds(unsynthesized_bounds, params)
+            } else {
+                self.platform_text_system.glyph_raster_bounds(params)?
+            };
             raster_bounds.insert(params.clone(), bounds);
             Ok(bounds)
         }
     }
 
+    /// Rasterize a glyph. Synthesized styles are applied to the glyph's coverage mask after the
+    /// platform rasterizes it, so that they look the same on every platform.
     pub(crate) fn rasterize_glyph(
         &self,
         params: &RenderGlyphParams,
     ) -> Result<(Size<DevicePixels>, Vec<u8>)> {
-        let raster_bounds = self.raster_bounds(params)?;
-        self.platform_text_system
-            .rasterize_glyph(params, raster_bounds)
+        if params.is_synthesized() {
+            let unsynthesized_params = params.without_synthesis();
+            let raster_bounds = self.raster_bounds(&unsynthesized_params)?;
+            let (raster_size, raster) = self
+                .platform_text_system
+                .rasterize_glyph(&unsynthesized_params, raster_bounds)?;
+            Ok(synthesize_raster(
+                raster_bounds.origin.y,
+                raster_size,
+                raster,
+                params,
+            ))
+        } else {
+            let raster_bounds = self.raster_bounds(params)?;
+            self.platform_text_system
+                .rasterize_glyph(params, raster_bounds)
+        }
+    }
+
+    /// Rasterize the glyphs for the given characters into `atlas`, in every horizontal and
+    /// vertical subpixel variant the rendering mode uses at the given scale factor, so that
+    /// painting them later doesn't have to. With subpixel rendering that's 16 rasters per glyph.
+    /// Glyphs that are already in the atlas are skipped, and no more than
+    /// [`MAX_PREWARMED_RASTERS`] are built, after which the remaining characters are ignored.
+    pub(crate) fn prewarm_glyphs(
+        &self,
+        font: &Font,
+        font_size: Pixels,
+        scale_factor: f32,
+        rendering_mode: TextRenderingMode,
+        atlas: &dyn PlatformAtlas,
+        chars: impl Iterator<Item = char>,
+    ) -> Result<()> {
+        let font_id = self.resolve_font(font);
+        let subpixel_variants = match rendering_mode {
+            TextRenderingMode::Subpixel => SUBPIXEL_VARIANTS,
+            TextRenderingMode::Grayscale | TextRenderingMode::Aliased => 1,
+        };
+        let mut rasters_built = 0;
+        for ch in chars {
+            if rasters_built >= MAX_PREWARMED_RASTERS {
+                break;
+            }
+            let Some(glyph_id) = self.platform_text_system.glyph_for_char(font_id, ch) else {
+                continue;
+            };
+            // Baselines aren't necessarily on whole pixels, so every vertical variant is used too.
+            let variants = (0..subpixel_variants)
+                .flat_map(|y| (0..subpixel_variants).map(move |x| Point { x, y }));
+            for subpixel_variant in variants {
+                let params = RenderGlyphParams {
+                    font_id,
+                    glyph_id,
+                    font_size,
+                    subpixel_variant,
+                    scale_factor,
+                    is_emoji: false,
+                    rendering_mode,
+                    synthetic_bold: None,
+                    synthetic_oblique: None,
+                };
+                if self.raster_bounds(&params)?.is_zero() {
+                    continue;
+                }
+                atlas.get_or_insert_with(&params.clone().into(), &mut || {
+                    let (size, bytes) = self.rasterize_glyph(&params)?;
+                    rasters_built += 1;
+                    Ok(Some((size, Cow::Owned(bytes))))
+                })?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -301,7 +641,11 @@ pub struct WindowTextSystem {
 impl WindowTextSystem {
     pub(crate) fn new(text_system: Arc<TextSystem>) -> Self {
         Self {
-            line_layout_cache: LineLayoutCache::new(text_system.platform_text_system.clone()),
+            line_layout_cache: LineLayoutCache::new(
+                text_system.platform_text_system.clone(),
+                text_system.line_layout_cache_budget.clone(),
+                text_system.shared_line_layouts.clone(),
+            ),
             text_system,
         }
     }
@@ -339,6 +683,8 @@ impl WindowTextSystem {
         for run in runs {
             if let Some(last_run) = decoration_runs.last_mut() {
                 if last_run.color == run.color
+                    && last_run.font_weight == run.font.weight
+                    && last_run.font_style == run.font.style
                     && last_run.underline == run.underline
                     && last_run.strikethrough == run.strikethrough
                     && last_run.background_color == run.background_color
@@ -350,6 +696,8 @@ impl WindowTextSystem {
             decoration_runs.push(DecorationRun {
                 len: run.len as u32,
                 color: run.color,
+                font_weight: run.font.weight,
+                font_style: run.font.style,
                 background_color: run.background_color,
                 underline: run.underline,
                 strikethrough: run.strikethrough,
@@ -362,20 +710,35 @@ impl WindowTextSystem {
             layout,
             text,
             decoration_runs,
+            invisibles: None,
+            background_corner_radius: px(0.),
+            marked_range: None,
         })
     }
 
     /// Shape a multi line string of text, at the given font_size, for painting to the screen.
     /// Subsets of the text can be styled independently with the `runs` parameter.
-    /// If `wrap_width` is provided, the line breaks will be adjusted to fit within the given width.
+    /// Each line is wrapped as `wrap` describes, see [`WrapOptions`].
     pub fn shape_text(
         &self,
         text: SharedString,
         font_size: Pixels,
         runs: &[TextRun],
-        wrap_width: Option<Pixels>,
+        wrap: WrapOptions,
     ) -> Result<SmallVec<[WrappedLine; 1]>> {
-        let mut runs = runs.iter().cloned().peekable();
+        // Most text is a short label in a single style, which doesn't need its runs split
+        // across lines.
+        if let [run] = runs {
+            if run.len == text.len() && !text.contains('\n') {
+                return Ok(smallvec![
+                    self.shape_single_run_line(text, font_size, run, wrap)
+                ]);
+            }
+        }
+
+        let mut runs = runs.iter().peekable();
+        // The number of bytes at the start of the next run that belong to previous lines.
+        let mut run_offset = 0;
         let mut font_runs = self.font_runs_pool.lock().pop().unwrap_or_default();
 
         let mut lines = SmallVec::new();
@@ -384,28 +747,38 @@ impl WindowTextSystem {
         let mut process_line = |line_text: SharedString| {
             let line_end = line_start + line_text.len();
 
-            let mut last_font: Option<Font> = None;
+            let mut last_font: Option<(&Font, FontId)> = None;
             let mut decoration_runs = SmallVec::<[DecorationRun; 32]>::new();
             let mut run_start = line_start;
             while run_start < line_end {
-                let Some(run) = runs.peek_mut() else {
+                let Some(&run) = runs.peek() else {
                     break;
                 };
 
-                let run_len_within_line = cmp::min(line_end, run_start + run.len) - run_start;
+                let run_len = run.len - run_offset;
+                let run_len_within_line = cmp::min(line_end, run_start + run_len) - run_start;
 
-                if last_font == Some(run.font.clone()) {
-                    font_runs.last_mut().unwrap().len += run_len_within_line;
-                } else {
-                    last_font = Some(run.font.clone());
-                    font_runs.push(FontRun {
-                        len: run_len_within_line,
-                        font_id: self.resolve_font(&run.font),
-                    });
-                }
+                let font_id = match last_font {
+                    Some((last_font, font_id)) if last_font == &run.font => font_id,
+                    _ => {
+                        let font_id = self.resolve_font(&run.font);
+                        last_font = Some((&run.font, font_id));
+                        font_id
+                    }
+                };
+                let run_start_in_line = run_start - line_start;
+                self.push_font_runs(
+                    &line_text,
+                    run_start_in_line..run_start_in_line + run_len_within_line,
+                    &run.font,
+                    font_id,
+                    &mut font_runs,
+                );
 
                 if decoration_runs.last().map_or(false, |last_run| {
                     last_run.color == run.color
+                        && last_run.font_weight == run.font.weight
+                        && last_run.font_style == run.font.style
                         && last_run.underline == run.underline
                         && last_run.strikethrough == run.strikethrough
                         && last_run.background_color == run.background_color
@@ -415,37 +788,49 @@ impl WindowTextSystem {
                     decoration_runs.push(DecorationRun {
                         len: run_len_within_line as u32,
                         color: run.color,
+                        font_weight: run.font.weight,
+                        font_style: run.font.style,
                         background_color: run.background_color,
                         underline: run.underline,
                         strikethrough: run.strikethrough,
                     });
                 }
 
-                if run_len_within_line == run.len {
+                if run_len_within_line == run_len {
                     runs.next();
+                    run_offset = 0;
                 } else {
                     // Preserve the remainder of the run for the next line
-                    run.len -= run_len_within_line;
+                    run_offset += run_len_within_line;
                 }
                 run_start += run_len_within_line;
             }
 
-            let layout = self
-                .line_layout_cache
-                .layout_wrapped_line(&line_text, font_size, &font_runs, wrap_width);
+            let layout = self.line_layout_cache.layout_wrapped_line(
+                &line_text,
+                font_size,
+                &font_runs,
+                wrap.width,
+                wrap.indent,
+                wrap.mode,
+            );
 
             lines.push(WrappedLine {
                 layout,
                 decoration_runs,
                 text: line_text,
+                invisibles: None,
+                background_corner_radius: px(0.),
+                marked_range: None,
             });
 
             // Skip `\n` character.
             line_start = line_end + 1;
-            if let Some(run) = runs.peek_mut() {
-                run.len = run.len.saturating_sub(1);
-                if run.len == 0 {
+            if let Some(run) = runs.peek() {
+                run_offset += 1;
+                if run_offset >= run.len {
                     runs.next();
+                    run_offset = 0;
                 }
             }
 
@@ -475,10 +860,55 @@ impl WindowTextSystem {
         Ok(lines)
     }
 
+    fn shape_single_run_line(
+        &self,
+        text: SharedString,
+        font_size: Pixels,
+        run: &TextRun,
+        wrap: WrapOptions,
+    ) -> WrappedLine {
+        let mut font_runs = self.font_runs_pool.lock().pop().unwrap_or_default();
+        let font_id = self.resolve_font(&run.font);
+        self.push_font_runs(&text, 0..text.len(), &run.font, font_id, &mut font_runs);
+        let layout = self.line_layout_cache.layout_wrapped_line(
+            &text,
+            font_size,
+            &font_runs,
+            wrap.width,
+            wrap.indent,
+            wrap.mode,
+        );
+        font_runs.clear();
+        self.font_runs_pool.lock().push(font_runs);
+
+        WrappedLine {
+            layout,
+            decoration_runs: smallvec![DecorationRun {
+                len: run.len as u32,
+                color: run.color,
+                font_weight: run.font.weight,
+                font_style: run.font.style,
+                background_color: run.background_color,
+                underline: run.underline,
+                strikethrough: run.strikethrough,
+            }],
+            text,
+            invisibles: None,
+            background_corner_radius: px(0.),
+            marked_range: None,
+        }
+    }
+
     pub(crate) fn finish_frame(&self) {
         self.line_layout_cache.finish_frame()
     }
 
+    /// Get statistics about how text layouts are being cached and reused in this window.
+    /// This is useful for finding text that is needlessly laid out again every frame.
+    pub fn layout_cache_stats(&self) -> LineLayoutCacheStats {
+        self.line_layout_cache.stats()
+    }
+
     /// Layout the given line of text, at the given font_size.
     /// Subsets of the line can be styled independently with the `runs` parameter.
     /// Generally, you should prefer to use `TextLayout::shape_line` instead, which
@@ -490,18 +920,17 @@ impl WindowTextSystem {
         runs: &[TextRun],
     ) -> Result<Arc<LineLayout>> {
         let mut font_runs = self.font_runs_pool.lock().pop().unwrap_or_default();
+        let mut run_start = 0;
         for run in runs.iter() {
             let font_id = self.resolve_font(&run.font);
-            if let Some(last_run) = font_runs.last_mut() {
-                if last_run.font_id == font_id {
-                    last_run.len += run.len;
-                    continue;
-                }
-            }
-            font_runs.push(FontRun {
-                len: run.len,
+            self.push_font_runs(
+                text,
+                run_start..run_start + run.len,
+                &run.font,
                 font_id,
-            });
+                &mut font_runs,
+            );
+            run_start += run.len;
         }
 
         let layout = self
@@ -515,12 +944,33 @@ impl WindowTextSystem {
     }
 }
 
+fn push_font_run(font_runs: &mut Vec<FontRun>, len: usize, font_id: FontId) {
+    if let Some(last_run) = font_runs.last_mut() {
+        if last_run.font_id == font_id {
+            last_run.len += len;
+            return;
+        }
+    }
+    font_runs.push(FontRun { len, font_id });
+}
+
 #[derive(Hash, Eq, PartialEq)]
 struct FontIdWithSize {
     font_id: FontId,
     font_size: Pixels,
 }
 
+/// A character in a requested font. The fallback for it is looked up with the requested font's
+/// weight, style and width, so they can change the result even when the font ID is the same.
+#[derive(Clone, Copy, Hash, Eq, PartialEq)]
+struct GlyphFallbackKey {
+    font_id: FontId,
+    weight: FontWeight,
+    style: FontStyle,
+    width: FontWidth,
+    ch: char,
+}
+
 /// A handle into the text system, which can be used to compute the wrapped layout of text
 pub struct LineWrapperHandle {
     wrapper: Option<LineWrapper>,
@@ -557,7 +1007,7 @@ impl DerefMut for LineWrapperHandle {
 
 /// The degree of blackness or stroke thickness of a font. This value ranges from 100.0 to 900.0,
 /// with 400.0 as normal.
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize, Serialize, JsonSchema)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize)]
 pub struct FontWeight(pub f32);
 
 impl Default for FontWeight {
@@ -594,23 +1044,226 @@ impl FontWeight {
     pub const EXTRA_BOLD: FontWeight = FontWeight(800.0);
     /// Black weight (900), the thickest value.
     pub const BLACK: FontWeight = FontWeight(900.0);
+
+    const NAMED: [(&'static str, FontWeight); 9] = [
+        ("thin", FontWeight::THIN),
+        ("extralight", FontWeight::EXTRA_LIGHT),
+        ("light", FontWeight::LIGHT),
+        ("normal", FontWeight::NORMAL),
+        ("medium", FontWeight::MEDIUM),
+        ("semibold", FontWeight::SEMIBOLD),
+        ("bold", FontWeight::BOLD),
+        ("extrabold", FontWeight::EXTRA_BOLD),
+        ("black", FontWeight::BLACK),
+    ];
+
+    /// Create a weight from a numeric value, clamping it to the valid range of 100.0 to 900.0.
+    pub fn clamped(value: f32) -> Self {
+        if !(100.0..=900.0).contains(&value) {
+            log::warn!("font weight {value} is outside of 100-900, clamping it");
+        }
+        FontWeight(value.clamp(100.0, 900.0))
+    }
+
+    /// The name of this weight, if it matches one of the named constants.
+    pub fn name(&self) -> Option<&'static str> {
+        Self::NAMED
+            .iter()
+            .find(|(_, weight)| weight == self)
+            .map(|(name, _)| *name)
+    }
+}
+
+impl std::str::FromStr for FontWeight {
+    type Err = anyhow::Error;
+
+    /// Parses either a number or one of the nine weight names, ignoring case and any `-`, `_`
+    /// or spaces, so that "Semi-Bold" and "semibold" are equivalent.
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_' | ' '))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if let Some((_, weight)) = Self::NAMED.iter().find(|(named, _)| *named == name) {
+            return Ok(*weight);
+        }
+
+        let value = s
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| anyhow!("invalid font weight {s:?}"))?;
+        Ok(Self::clamped(value))
+    }
+}
+
+impl Display for FontWeight {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FontWeight {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum FontWeightContent {
+            Number(f32),
+            Name(String),
+        }
+
+        match FontWeightContent::deserialize(deserializer)? {
+            FontWeightContent::Number(value) => Ok(FontWeight::clamped(value)),
+            FontWeightContent::Name(name) => name.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl JsonSchema for FontWeight {
+    fn schema_name() -> String {
+        "FontWeight".into()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, SchemaObject, SingleOrVec};
+
+        let mut number_schema = SchemaObject::default();
+        number_schema.instance_type = Some(SingleOrVec::Single(Box::new(InstanceType::Number)));
+        number_schema.number().minimum = Some(100.0);
+        number_schema.number().maximum = Some(900.0);
+
+        let mut name_schema = SchemaObject::default();
+        name_schema.instance_type = Some(SingleOrVec::Single(Box::new(InstanceType::String)));
+        name_schema.enum_values =
+            Some(Self::NAMED.iter().map(|(name, _)| (*name).into()).collect());
+
+        let mut schema = SchemaObject::default();
+        schema.subschemas().any_of = Some(vec![number_schema.into(), name_schema.into()]);
+        schema.into()
+    }
+}
+
+/// The width of a font's glyphs relative to its normal face, as a percentage. This value ranges
+/// from 50.0 to 200.0, with 100.0 as normal.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize, Serialize, JsonSchema)]
+pub struct FontWidth(pub f32);
+
+impl Default for FontWidth {
+    #[inline]
+    fn default() -> FontWidth {
+        FontWidth::NORMAL
+    }
+}
+
+impl Hash for FontWidth {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u32(u32::from_be_bytes(self.0.to_be_bytes()));
+    }
+}
+
+impl Eq for FontWidth {}
+
+impl FontWidth {
+    /// Ultra-condensed width (50%), the narrowest value.
+    pub const ULTRA_CONDENSED: FontWidth = FontWidth(50.0);
+    /// Extra-condensed width (62.5%).
+    pub const EXTRA_CONDENSED: FontWidth = FontWidth(62.5);
+    /// Condensed width (75%).
+    pub const CONDENSED: FontWidth = FontWidth(75.0);
+    /// Semi-condensed width (87.5%).
+    pub const SEMI_CONDENSED: FontWidth = FontWidth(87.5);
+    /// Normal width (100%).
+    pub const NORMAL: FontWidth = FontWidth(100.0);
+    /// Semi-expanded width (112.5%).
+    pub const SEMI_EXPANDED: FontWidth = FontWidth(112.5);
+    /// Expanded width (125%).
+    pub const EXPANDED: FontWidth = FontWidth(125.0);
+    /// Extra-expanded width (150%).
+    pub const EXTRA_EXPANDED: FontWidth = FontWidth(150.0);
+    /// Ultra-expanded width (200%), the widest value.
+    pub const ULTRA_EXPANDED: FontWidth = FontWidth(200.0);
 }
 
 /// Allows italic or oblique faces to be selected.
-#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[serde(from = "SerializedFontStyle", into = "SerializedFontStyle")]
 pub enum FontStyle {
     /// A face that is neither italic not obliqued.
     #[default]
     Normal,
     /// A form that is generally cursive in nature.
     Italic,
-    /// A typically-sloped version of the regular face.
-    Oblique,
+    /// A typically-sloped version of the regular face. When the family has no such face and the
+    /// slant is synthesized, it is slanted by the given angle in degrees, or by a default angle
+    /// if there is none. Platform font matching only distinguishes the style, not the angle.
+    Oblique(Option<f32>),
+}
+
+impl Hash for FontStyle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        if let FontStyle::Oblique(angle) = self {
+            angle.map(f32::to_bits).hash(state);
+        }
+    }
 }
 
+impl Eq for FontStyle {}
+
 impl Display for FontStyle {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        Debug::fmt(self, f)
+        match self {
+            FontStyle::Oblique(Some(angle)) => write!(f, "Oblique {angle}deg"),
+            FontStyle::Oblique(None) => f.write_str("Oblique"),
+            _ => Debug::fmt(self, f),
+        }
+    }
+}
+
+/// How a [`FontStyle`] is written in settings and themes: `"normal"`, `"italic"` or
+/// `"oblique"`, or `{ "oblique": 10 }` for an oblique style with an angle.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum SerializedFontStyle {
+    Name(FontStyleName),
+    Oblique { oblique: f32 },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum FontStyleName {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl From<SerializedFontStyle> for FontStyle {
+    fn from(style: SerializedFontStyle) -> Self {
+        match style {
+            SerializedFontStyle::Name(FontStyleName::Normal) => FontStyle::Normal,
+            SerializedFontStyle::Name(FontStyleName::Italic) => FontStyle::Italic,
+            SerializedFontStyle::Name(FontStyleName::Oblique) => FontStyle::Oblique(None),
+            SerializedFontStyle::Oblique { oblique } => FontStyle::Oblique(Some(oblique)),
+        }
+    }
+}
+
+impl From<FontStyle> for SerializedFontStyle {
+    fn from(style: FontStyle) -> Self {
+        match style {
+            FontStyle::Normal => SerializedFontStyle::Name(FontStyleName::Normal),
+            FontStyle::Italic => SerializedFontStyle::Name(FontStyleName::Italic),
+            FontStyle::Oblique(None) => SerializedFontStyle::Name(FontStyleName::Oblique),
+            FontStyle::Oblique(Some(oblique)) => SerializedFontStyle::Oblique { oblique },
+        }
     }
 }
 
@@ -636,6 +1289,23 @@ pub struct TextRun {
 #[repr(C)]
 pub struct GlyphId(pub(crate) u32);
 
+/// How glyphs are positioned and antialiased when they're rasterized, see
+/// [`crate::WindowContext::set_text_rendering_mode`]. Glyphs are always rasterized as coverage
+/// masks, so none of these modes use per-channel (LCD) antialiasing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TextRenderingMode {
+    /// Glyphs are antialiased, and positioned at fractions of a device pixel so that their
+    /// spacing matches the layout exactly.
+    #[default]
+    Subpixel,
+    /// Glyphs are antialiased, and snapped to whole device pixels. This keeps them crisper, e.g.
+    /// on rotated displays or at fractional scale factors, at the cost of uneven spacing.
+    Grayscale,
+    /// Glyphs are neither antialiased nor positioned at fractions of a device pixel.
+    Aliased,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct RenderGlyphParams {
     pub(crate) font_id: FontId,
@@ -644,6 +1314,23 @@ pub(crate) struct RenderGlyphParams {
     pub(crate) subpixel_variant: Point<u8>,
     pub(crate) scale_factor: f32,
     pub(crate) is_emoji: bool,
+    pub(crate) rendering_mode: TextRenderingMode,
+    pub(crate) synthetic_bold: Option<Pixels>,
+    pub(crate) synthetic_oblique: Option<f32>,
+}
+
+impl RenderGlyphParams {
+    fn is_synthesized(&self) -> bool {
+        self.synthetic_bold.is_some() || self.synthetic_oblique.is_some()
+    }
+
+    fn without_synthesis(&self) -> Self {
+        Self {
+            synthetic_bold: None,
+            synthetic_oblique: None,
+            ..self.clone()
+        }
+    }
 }
 
 impl Eq for RenderGlyphParams {}
@@ -655,7 +1342,118 @@ impl Hash for RenderGlyphParams {
         self.font_size.0.to_bits().hash(state);
         self.subpixel_variant.hash(state);
         self.scale_factor.to_bits().hash(state);
+        self.rendering_mode.hash(state);
+        self.synthetic_bold
+            .map(|strength| strength.0.to_bits())
+            .hash(state);
+        self.synthetic_oblique.map(f32::to_bits).hash(state);
+    }
+}
+
+/// A segment of a glyph's outline, as returned by the platform text system. Points are in font
+/// units, with the y axis pointing up from the baseline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum OutlineSegment {
+    MoveTo(Point<f32>),
+    LineTo(Point<f32>),
+    QuadTo {
+        ctrl: Point<f32>,
+        to: Point<f32>,
+    },
+    CubicTo {
+        ctrl1: Point<f32>,
+        ctrl2: Point<f32>,
+        to: Point<f32>,
+    },
+    Close,
+}
+
+/// Converts a glyph outline into a path, scaling it from font units by `scale`, flipping it so
+/// that y points down, and placing its baseline origin at `origin`. Contours are closed
+/// implicitly, and cubic curves are approximated by two quadratic curves each.
+/// Returns `None` if the outline has nothing to fill.
+fn path_for_outline(
+    outline: &[OutlineSegment],
+    scale: f32,
+    origin: Point<Pixels>,
+) -> Option<Path<Pixels>> {
+    if outline
+        .iter()
+        .all(|segment| matches!(segment, OutlineSegment::MoveTo(_) | OutlineSegment::Close))
+    {
+        return None;
+    }
+
+    let to_pixels = |position: Point<f32>| {
+        point(
+            origin.x + px(position.x * scale),
+            origin.y - px(position.y * scale),
+        )
+    };
+    let mid = |a: Point<f32>, b: Point<f32>| point((a.x + b.x) / 2., (a.y + b.y) / 2.);
+    // The control point of the quadratic curve closest to the cubic curve from `from` to `to`.
+    let quad_ctrl = |from: Point<f32>, ctrl1: Point<f32>, ctrl2: Point<f32>, to: Point<f32>| {
+        point(
+            (3. * (ctrl1.x + ctrl2.x) - from.x - to.x) / 4.,
+            (3. * (ctrl1.y + ctrl2.y) - from.y - to.y) / 4.,
+        )
+    };
+
+    let mut current = match outline[0] {
+        OutlineSegment::MoveTo(to) => to,
+        _ => Point::default(),
+    };
+    let mut contour_start = current;
+    let mut path = Path::new(to_pixels(current));
+    for segment in outline {
+        match *segment {
+            OutlineSegment::MoveTo(to) => {
+                if current != contour_start {
+                    path.line_to(to_pixels(contour_start));
+                }
+                path.move_to(to_pixels(to));
+                current = to;
+                contour_start = to;
+            }
+            OutlineSegment::LineTo(to) => {
+                path.line_to(to_pixels(to));
+                current = to;
+            }
+            OutlineSegment::QuadTo { ctrl, to } => {
+                path.curve_to(to_pixels(to), to_pixels(ctrl));
+                current = to;
+            }
+            OutlineSegment::CubicTo { ctrl1, ctrl2, to } => {
+                // Split the curve in half, and approximate each half with a quadratic curve.
+                let from = current;
+                let ctrl01 = mid(from, ctrl1);
+                let ctrl12 = mid(ctrl1, ctrl2);
+                let ctrl23 = mid(ctrl2, to);
+                let ctrl012 = mid(ctrl01, ctrl12);
+                let ctrl123 = mid(ctrl12, ctrl23);
+                let split = mid(ctrl012, ctrl123);
+                path.curve_to(
+                    to_pixels(split),
+                    to_pixels(quad_ctrl(from, ctrl01, ctrl012, split)),
+                );
+                path.curve_to(
+                    to_pixels(to),
+                    to_pixels(quad_ctrl(split, ctrl123, ctrl23, to)),
+                );
+                current = to;
+            }
+            OutlineSegment::Close => {
+                if current != contour_start {
+                    path.line_to(to_pixels(contour_start));
+                    current = contour_start;
+                }
+            }
+        }
+    }
+    if current != contour_start {
+        path.line_to(to_pixels(contour_start));
     }
+    Some(path)
 }
 
 /// The parameters for rendering an emoji glyph.
@@ -679,30 +1477,46 @@ impl Hash for RenderEmojiParams {
 }
 
 /// The configuration details for identifying a specific font.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+///
+/// When deserializing, every field except `family` is optional.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub struct Font {
     /// The font family name.
     ///
     /// The special name ".SystemUIFont" is used to identify the system UI font, which varies based on platform.
+    /// The CSS generic names "serif", "sans-serif", "monospace", "cursive" and "fantasy" resolve to the
+    /// platform's default family for each.
     pub family: SharedString,
 
     /// The font features to use.
+    #[serde(default)]
     pub features: FontFeatures,
 
     /// The font weight.
+    #[serde(default)]
     pub weight: FontWeight,
 
     /// The font style.
+    #[serde(default)]
     pub style: FontStyle,
+
+    /// The font width. If the family has no face with this width, the nearest available width
+    /// is used.
+    #[serde(default)]
+    pub width: FontWidth,
 }
 
 /// Get a [`Font`] for a given name.
+///
+/// Besides installed family names, the CSS generic names "serif", "sans-serif", "monospace",
+/// "cursive" and "fantasy" are accepted and resolve to the platform's default family for each.
 pub fn font(family: impl Into<SharedString>) -> Font {
     Font {
         family: family.into(),
         features: FontFeatures::default(),
         weight: FontWeight::default(),
         style: FontStyle::default(),
+        width: FontWidth::default(),
     }
 }
 
@@ -718,6 +1532,53 @@ impl Font {
         self.style = FontStyle::Italic;
         self
     }
+
+    /// Set this Font's family, keeping its other properties
+    pub fn with_family(mut self, family: impl Into<SharedString>) -> Self {
+        self.family = family.into();
+        self
+    }
+
+    /// Set this Font's weight
+    pub fn with_weight(mut self, weight: FontWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Merge the given features into this Font's features, replacing the values of any tags
+    /// that are already set
+    pub fn with_features(mut self, features: FontFeatures) -> Self {
+        for (tag, value) in features.tag_value_list() {
+            self.features = self.features.with_feature(tag, *value);
+        }
+        self
+    }
+}
+
+/// A CSS generic font family name, which each platform resolves to one of its own families.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GenericFontFamily {
+    Serif,
+    SansSerif,
+    Monospace,
+    Cursive,
+    Fantasy,
+}
+
+impl GenericFontFamily {
+    /// Returns the generic family for the given name, ignoring case.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        [
+            ("serif", Self::Serif),
+            ("sans-serif", Self::SansSerif),
+            ("monospace", Self::Monospace),
+            ("cursive", Self::Cursive),
+            ("fantasy", Self::Fantasy),
+        ]
+        .into_iter()
+        .find(|(generic_name, _)| name.eq_ignore_ascii_case(generic_name))
+        .map(|(_, generic)| generic)
+    }
 }
 
 /// A struct for storing font metrics.
@@ -775,7 +1636,8 @@ impl FontMetrics {
         Pixels((self.underline_position / self.units_per_em as f32) * font_size.0)
     }
 
-    /// Returns the suggested thickness of the underline in pixels.
+    /// Returns the suggested thickness of the underline in pixels. Text is painted with this
+    /// thickness for underlines and strikethroughs whose style leaves the thickness at zero.
     pub fn underline_thickness(&self, font_size: Pixels) -> Pixels {
         Pixels((self.underline_thickness / self.units_per_em as f32) * font_size.0)
     }
@@ -795,3 +1657,705 @@ impl FontMetrics {
         (self.bounding_box / self.units_per_em as f32 * font_size.0).map(px)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point, size, TestAppContext, TestAtlas, TestDispatcher, TestTextSystem};
+    use rand::prelude::*;
+
+    /// A text system with the fixed metrics of [`TestTextSystem`].
+    fn test_text_system() -> WindowTextSystem {
+        WindowTextSystem::new(Arc::new(TextSystem::new(Arc::new(TestTextSystem))))
+    }
+
+    /// A text system backed by the platform, with Zed Plex Mono loaded, for tests of which
+    /// characters a real font can render.
+    fn plex_text_system() -> WindowTextSystem {
+        let dispatcher = TestDispatcher::new(StdRng::seed_from_u64(0));
+        let cx = TestAppContext::new(dispatcher, None);
+        cx.text_system()
+            .add_fonts(vec![std::fs::read(
+                "../../assets/fonts/plex-mono/ZedPlexMono-Regular.ttf",
+            )
+            .unwrap()
+            .into()])
+            .unwrap();
+        WindowTextSystem::new(cx.text_system().clone())
+    }
+
+    fn run(len: usize) -> TextRun {
+        TextRun {
+            len,
+            font: font(TestTextSystem::FONT_FAMILY),
+            color: Default::default(),
+            background_color: None,
+            underline: None,
+            strikethrough: None,
+        }
+    }
+
+    /// Shapes text without newlines in the test font at 16px, where most characters are 8px
+    /// wide.
+    fn shape_line(
+        text_system: &WindowTextSystem,
+        text: &'static str,
+        wrap: WrapOptions,
+    ) -> WrappedLine {
+        let mut lines = text_system
+            .shape_text(text.into(), px(16.), &[run(text.len())], wrap)
+            .unwrap();
+        assert_eq!(lines.len(), 1);
+        lines.pop().unwrap()
+    }
+
+    fn wrap_width(width: Pixels) -> WrapOptions {
+        WrapOptions {
+            width: Some(width),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rendering_mode_is_part_of_glyph_key() {
+        let params = RenderGlyphParams {
+            font_id: FontId(0),
+            glyph_id: GlyphId(1),
+            font_size: px(14.),
+            subpixel_variant: Point::default(),
+            scale_factor: 2.,
+            is_emoji: false,
+            rendering_mode: TextRenderingMode::Subpixel,
+            synthetic_bold: None,
+            synthetic_oblique: None,
+        };
+        let keys = [
+            TextRenderingMode::Subpixel,
+            TextRenderingMode::Grayscale,
+            TextRenderingMode::Aliased,
+        ]
+        .into_iter()
+        .map(|rendering_mode| RenderGlyphParams {
+            rendering_mode,
+            ..params.clone()
+        })
+        .collect::<collections::HashSet<_>>();
+        assert_eq!(keys.len(), 3);
+    }
+
+    #[test]
+    fn test_synthesis_is_part_of_glyph_key() {
+        let text_system = TextSystem::new(Arc::new(TestTextSystem));
+        let font_id = text_system.resolve_font(&font(TestTextSystem::FONT_FAMILY));
+        let glyph_id = text_system
+            .platform_text_system
+            .glyph_for_char(font_id, 'l')
+            .unwrap();
+        let regular = RenderGlyphParams {
+            font_id,
+            glyph_id,
+            font_size: px(16.),
+            subpixel_variant: Point::default(),
+            scale_factor: 2.,
+            is_emoji: false,
+            rendering_mode: TextRenderingMode::Subpixel,
+            synthetic_bold: None,
+            synthetic_oblique: None,
+        };
+        let bold = RenderGlyphParams {
+            synthetic_bold: Some(px(1.)),
+            ..regular.clone()
+        };
+        let oblique = RenderGlyphParams {
+            synthetic_oblique: Some(0.25),
+            ..regular.clone()
+        };
+        let keys = [regular.clone(), bold.clone(), oblique]
+            .into_iter()
+            .collect::<collections::HashSet<_>>();
+        assert_eq!(keys.len(), 3);
+
+        // The glyph's box runs from 1.6 to 14.4 device pixels, which round to 2 and 14.
+        // Emboldening by a pixel smears it 2 device pixels to the right at this scale factor.
+        let (regular_size, _) = text_system.rasterize_glyph(&regular).unwrap();
+        let (bold_size, _) = text_system.rasterize_glyph(&bold).unwrap();
+        assert_eq!(regular_size.width, DevicePixels(12));
+        assert_eq!(bold_size.width, DevicePixels(14));
+        assert_eq!(
+            text_system.raster_bounds(&bold).unwrap().size.width,
+            DevicePixels(14)
+        );
+    }
+
+    #[test]
+    fn test_path_for_outline() {
+        let empty = [OutlineSegment::MoveTo(point(0., 0.)), OutlineSegment::Close];
+        assert!(path_for_outline(&empty, 1., Point::default()).is_none());
+
+        // A square of 1000 font units sitting on the baseline, scaled down by 100.
+        let square = [
+            OutlineSegment::MoveTo(point(0., 0.)),
+            OutlineSegment::LineTo(point(1000., 0.)),
+            OutlineSegment::LineTo(point(1000., 1000.)),
+            OutlineSegment::LineTo(point(0., 1000.)),
+            OutlineSegment::Close,
+        ];
+        let path = path_for_outline(&square, 0.01, point(px(5.), px(20.))).unwrap();
+        assert_eq!(
+            path.bounds,
+            Bounds::new(point(px(5.), px(10.)), size(px(10.), px(10.)))
+        );
+    }
+
+    #[test]
+    fn test_shape_text_with_trailing_newline() {
+        let text_system = test_text_system();
+        let line_height = px(20.);
+
+        let lines = text_system
+            .shape_text("a\n".into(), px(16.), &[run(2)], WrapOptions::default())
+            .unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].len(), 0);
+        assert_eq!(lines[1].size(line_height).height, line_height);
+        assert_eq!(
+            lines[1].position_for_index(0, line_height),
+            Some(point(px(0.), px(0.)))
+        );
+        assert_eq!(
+            lines[1].index_for_position(point(px(5.), px(10.)), line_height),
+            Err(0)
+        );
+
+        let lines = text_system
+            .shape_text("a\n\n".into(), px(16.), &[run(3)], WrapOptions::default())
+            .unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1].len(), 0);
+        assert_eq!(lines[2].len(), 0);
+        let height = lines
+            .iter()
+            .map(|line| line.size(line_height).height)
+            .fold(Pixels::ZERO, |a, b| a + b);
+        assert_eq!(height, line_height * 3.);
+    }
+
+    #[test]
+    fn test_shape_text_reuses_cached_layouts() {
+        let text_system = test_text_system();
+        let shape = || shape_line(&text_system, "hello world", wrap_width(px(40.))).layout;
+
+        let first = shape();
+        let second = shape();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Layouts used in the previous frame are carried over to the next one.
+        text_system.finish_frame();
+        let third = shape();
+        assert!(Arc::ptr_eq(&first, &third));
+
+        // Layouts not used during a frame are dropped.
+        text_system.finish_frame();
+        text_system.finish_frame();
+        let fourth = shape();
+        assert!(!Arc::ptr_eq(&first, &fourth));
+    }
+
+    #[test]
+    fn test_shape_text_with_wrap_indent() {
+        let text_system = test_text_system();
+        let shape = |wrap_indent| {
+            let wrap = WrapOptions {
+                indent: wrap_indent,
+                ..wrap_width(px(100.))
+            };
+            shape_line(&text_system, "aaaa aaaa aaaa aaaa", wrap)
+        };
+
+        let unindented = shape(None);
+        assert_eq!(unindented.wrap_boundaries().len(), 1);
+
+        // Continuation lines only have 60px left, so the text needs another line.
+        let indented = shape(Some(px(40.)));
+        assert_eq!(indented.wrap_indent(), px(40.));
+        assert_eq!(indented.wrap_boundaries().len(), 2);
+        assert!(!Arc::ptr_eq(&unindented.layout, &indented.layout));
+    }
+
+    #[test]
+    fn test_shape_text_with_soft_hyphen() {
+        let text_system = test_text_system();
+
+        // When the line isn't wrapped at it, the soft hyphen takes up no space.
+        let plain = shape_line(&text_system, "aaaabbbb", WrapOptions::default());
+        let hyphenated = shape_line(&text_system, "aaaa\u{ad}bbbb", WrapOptions::default());
+        assert_eq!(plain.width(), px(64.));
+        assert_eq!(hyphenated.width(), px(64.));
+
+        // Otherwise the line is wrapped just after it, as "aaaa-" fits in 40px.
+        let wrapped = shape_line(&text_system, "aaaa\u{ad}bbbb", wrap_width(px(48.)));
+        assert_eq!(wrapped.wrap_boundaries().len(), 1);
+        let boundary = wrapped.wrap_boundaries()[0];
+        assert_eq!(
+            wrapped.runs()[boundary.run_ix].glyphs[boundary.glyph_ix].index,
+            6
+        );
+    }
+
+    #[test]
+    fn test_shape_text_with_test_text_system() {
+        let text_system = test_text_system();
+        let line_height = px(12.);
+
+        let line = text_system
+            .shape_text(
+                "hello world".into(),
+                px(10.),
+                &[run(11)],
+                wrap_width(px(40.)),
+            )
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(line.unwrapped_layout.width, px(55.));
+        assert_eq!(line.size(line_height), size(px(40.), px(24.)));
+        assert_eq!(line.wrap_boundaries().len(), 1);
+        assert_eq!(
+            line.position_for_index(8, line_height),
+            Some(point(px(10.), line_height))
+        );
+        assert_eq!(
+            line.index_for_position(point(px(12.), px(14.)), line_height),
+            Ok(8)
+        );
+    }
+
+    #[test]
+    fn test_script_fallbacks_split_font_runs() {
+        let text_system = plex_text_system();
+        let primary = text_system.resolve_font(&font("Zed Plex Mono"));
+        let text = "ab直c";
+
+        let mut font_runs = Vec::new();
+        text_system.push_font_runs(
+            text,
+            0..text.len(),
+            &font("Zed Plex Mono"),
+            primary,
+            &mut font_runs,
+        );
+        assert_eq!(
+            font_runs,
+            [FontRun {
+                len: text.len(),
+                font_id: primary
+            }]
+        );
+
+        // When no configured fallback can render the character either, the primary font is kept.
+        text_system.set_script_fallbacks(UnicodeScript::Han, vec!["Zed Plex Mono".into()]);
+        font_runs.clear();
+        text_system.push_font_runs(
+            text,
+            0..text.len(),
+            &font("Zed Plex Mono"),
+            primary,
+            &mut font_runs,
+        );
+        assert_eq!(
+            font_runs,
+            [FontRun {
+                len: text.len(),
+                font_id: primary
+            }]
+        );
+    }
+
+    #[test]
+    fn test_script_fallback_with_glyph_splits_font_run() {
+        let text_system = plex_text_system();
+        text_system
+            .add_fonts(vec![std::fs::read(
+                "../../assets/fonts/plex-sans/ZedPlexSans-Regular.ttf",
+            )
+            .unwrap()
+            .into()])
+            .unwrap();
+        let primary = text_system.resolve_font(&font("Zed Plex Mono"));
+        let sans = text_system.font_id(&font("Zed Plex Sans")).unwrap();
+        // Plex Sans has Greek capitals, which Plex Mono doesn't.
+        let text = "abΩc";
+        let shape = || {
+            let mut font_runs = Vec::new();
+            text_system.push_font_runs(
+                text,
+                0..text.len(),
+                &font("Zed Plex Mono"),
+                primary,
+                &mut font_runs,
+            );
+            font_runs
+        };
+
+        text_system.set_script_fallbacks(UnicodeScript::Greek, vec!["Zed Plex Sans".into()]);
+        let expected = [
+            FontRun {
+                len: 2,
+                font_id: primary,
+            },
+            FontRun {
+                len: 'Ω'.len_utf8(),
+                font_id: sans,
+            },
+            FontRun {
+                len: 1,
+                font_id: primary,
+            },
+        ];
+        assert_eq!(shape(), expected);
+        assert_eq!(
+            text_system.glyph_fallbacks.read().get(&GlyphFallbackKey {
+                font_id: primary,
+                weight: FontWeight::default(),
+                style: FontStyle::default(),
+                width: FontWidth::default(),
+                ch: 'Ω',
+            }),
+            Some(&Some(sans))
+        );
+        // The second time, the cached choice is used.
+        assert_eq!(shape(), expected);
+
+        // Changing the fallbacks discards the cached choices.
+        text_system.set_script_fallbacks(UnicodeScript::Greek, Vec::new());
+        assert!(text_system.glyph_fallbacks.read().is_empty());
+        assert_eq!(
+            shape(),
+            [FontRun {
+                len: text.len(),
+                font_id: primary
+            }]
+        );
+    }
+
+    #[test]
+    fn test_glyph_fallbacks_cache() {
+        let text_system = TextSystem::new(Arc::new(TestTextSystem));
+        let font_id = text_system.resolve_font(&font("Zed Test"));
+        let push_font_runs = |text: &str, font: &Font| {
+            let mut font_runs = Vec::new();
+            text_system.push_font_runs(text, 0..text.len(), font, font_id, &mut font_runs);
+        };
+        text_system.set_script_fallbacks(UnicodeScript::Greek, vec!["Zed Test".into()]);
+
+        // Characters of scripts without fallbacks aren't cached.
+        push_font_runs("abc", &font("Zed Test"));
+        assert!(text_system.glyph_fallbacks.read().is_empty());
+
+        // The same character is cached separately for each style it's requested in, as the
+        // fallback face can differ even when the requested font ID doesn't.
+        push_font_runs("Ω", &font("Zed Test"));
+        push_font_runs("Ω", &font("Zed Test").bold());
+        push_font_runs("Ω", &font("Zed Test").bold());
+        assert_eq!(text_system.glyph_fallbacks.read().len(), 2);
+
+        // Adding fonts discards the cached choices.
+        text_system.add_fonts(Vec::new()).unwrap();
+        assert!(text_system.glyph_fallbacks.read().is_empty());
+
+        // The cache is cleared once it's full.
+        let greek = ('\u{3b1}'..='\u{3c9}').collect::<String>();
+        for weight in 1..=MAX_GLYPH_FALLBACKS / greek.chars().count() + 1 {
+            push_font_runs(
+                &greek,
+                &Font {
+                    weight: FontWeight(weight as f32),
+                    ..font("Zed Test")
+                },
+            );
+            assert!(text_system.glyph_fallbacks.read().len() <= MAX_GLYPH_FALLBACKS);
+        }
+    }
+
+    #[test]
+    fn test_default_fallbacks() {
+        let text_system = plex_text_system();
+        text_system
+            .add_fonts(vec![std::fs::read(
+                "../../assets/fonts/plex-sans/ZedPlexSans-Regular.ttf",
+            )
+            .unwrap()
+            .into()])
+            .unwrap();
+        let sans = text_system.font_id(&font("Zed Plex Sans")).unwrap();
+        let missing = font("A Family That Is Not Installed");
+        assert!(!text_system.resolve_face_info(&missing).family_matched);
+        assert_ne!(text_system.resolve_font(&missing), sans);
+
+        text_system.set_default_fallbacks(vec![missing.family.clone(), "Zed Plex Sans".into()]);
+        assert_eq!(text_system.resolve_font(&missing), sans);
+
+        // Characters the primary font can render don't consult the fallbacks.
+        let primary = text_system.resolve_font(&font("Zed Plex Mono"));
+        let mut font_runs = Vec::new();
+        text_system.push_font_runs("abc", 0..3, &font("Zed Plex Mono"), primary, &mut font_runs);
+        assert_eq!(
+            font_runs,
+            [FontRun {
+                len: 3,
+                font_id: primary
+            }]
+        );
+    }
+
+    #[test]
+    fn test_set_emoji_font() {
+        let text_system = plex_text_system();
+
+        text_system.set_emoji_font("Zed Plex Mono".into());
+        assert_eq!(
+            text_system
+                .script_fallbacks
+                .read()
+                .get(&UnicodeScript::Emoji),
+            Some(&vec![SharedString::from("Zed Plex Mono")])
+        );
+
+        text_system.set_emoji_font("A Family That Is Not Installed".into());
+        assert_eq!(
+            text_system
+                .script_fallbacks
+                .read()
+                .get(&UnicodeScript::Emoji),
+            None
+        );
+    }
+
+    #[test]
+    fn test_prewarm_glyphs() {
+        let text_system = TextSystem::new(Arc::new(TestTextSystem));
+        let atlas = TestAtlas::new();
+        let prewarm = || {
+            text_system
+                .prewarm_glyphs(
+                    &font("Zed Test"),
+                    px(16.),
+                    2.,
+                    TextRenderingMode::Subpixel,
+                    &atlas,
+                    "ab ".chars(),
+                )
+                .unwrap()
+        };
+
+        // Each visible glyph is built in every horizontal and vertical subpixel variant.
+        prewarm();
+        let variants = SUBPIXEL_VARIANTS as usize * SUBPIXEL_VARIANTS as usize;
+        assert_eq!(atlas.tile_count(), 2 * variants);
+
+        // Glyphs that are already in the atlas aren't built again.
+        prewarm();
+        assert_eq!(atlas.tile_count(), 2 * variants);
+
+        // A single call stops once it has built enough rasters for its whole budget of glyphs.
+        let chars = ('\u{4e00}'..).take(MAX_PREWARMED_RASTERS / variants + 10);
+        text_system
+            .prewarm_glyphs(
+                &font("Zed Test"),
+                px(16.),
+                2.,
+                TextRenderingMode::Subpixel,
+                &atlas,
+                chars,
+            )
+            .unwrap();
+        assert_eq!(atlas.tile_count(), 2 * variants + MAX_PREWARMED_RASTERS);
+    }
+
+    #[test]
+    fn test_font_width_uses_nearest_available_face() {
+        let text_system = plex_text_system();
+
+        // Plex Mono only ships a normal width, so every width should resolve to it.
+        let normal = text_system.font_id(&font("Zed Plex Mono")).unwrap();
+        for width in [FontWidth::CONDENSED, FontWidth::ULTRA_EXPANDED] {
+            let font = Font {
+                width,
+                ..font("Zed Plex Mono")
+            };
+            assert_eq!(text_system.font_id(&font).unwrap(), normal);
+        }
+    }
+
+    #[test]
+    fn test_font_weight_parsing() {
+        assert_eq!("bold".parse::<FontWeight>().unwrap(), FontWeight::BOLD);
+        assert_eq!(
+            "Semi-Bold".parse::<FontWeight>().unwrap(),
+            FontWeight::SEMIBOLD
+        );
+        assert_eq!(
+            "extra_light".parse::<FontWeight>().unwrap(),
+            FontWeight::EXTRA_LIGHT
+        );
+        assert_eq!("450".parse::<FontWeight>().unwrap(), FontWeight(450.));
+        assert_eq!("1000".parse::<FontWeight>().unwrap(), FontWeight::BLACK);
+        assert!("heavyish".parse::<FontWeight>().is_err());
+
+        let weights: Vec<FontWeight> = serde_json::from_str(r#"[50, 350, "BLACK"]"#).unwrap();
+        assert_eq!(
+            weights,
+            [FontWeight::THIN, FontWeight(350.), FontWeight::BLACK]
+        );
+        assert!(serde_json::from_str::<FontWeight>(r#""heavyish""#).is_err());
+
+        assert_eq!(FontWeight::SEMIBOLD.to_string(), "semibold");
+        assert_eq!(FontWeight(450.).to_string(), "450");
+    }
+
+    #[test]
+    fn test_font_serde_round_trip() {
+        let semibold_condensed = Font {
+            features: FontFeatures::default().ligatures(false).tnum(true),
+            weight: FontWeight::SEMIBOLD,
+            style: FontStyle::Italic,
+            width: FontWidth::CONDENSED,
+            ..font("Zed Plex Sans")
+        };
+        let json = serde_json::to_value(&semibold_condensed).unwrap();
+        assert_eq!(json["style"], "italic");
+        assert_eq!(
+            serde_json::from_value::<Font>(json).unwrap(),
+            semibold_condensed
+        );
+
+        let family_only: Font = serde_json::from_str(r#"{"family": "Zed Plex Mono"}"#).unwrap();
+        assert_eq!(family_only, font("Zed Plex Mono"));
+        assert!(serde_json::from_str::<Font>(r#"{"weight": "bold"}"#).is_err());
+    }
+
+    #[test]
+    fn test_font_style_serialization() {
+        for (json, style) in [
+            (r#""normal""#, FontStyle::Normal),
+            (r#""italic""#, FontStyle::Italic),
+            (r#""oblique""#, FontStyle::Oblique(None)),
+            (r#"{"oblique":10.0}"#, FontStyle::Oblique(Some(10.))),
+        ] {
+            assert_eq!(serde_json::from_str::<FontStyle>(json).unwrap(), style);
+            assert_eq!(serde_json::to_string(&style).unwrap(), json);
+        }
+        assert!(serde_json::from_str::<FontStyle>(r#""slanted""#).is_err());
+
+        assert_eq!(FontStyle::Oblique(Some(10.)).to_string(), "Oblique 10deg");
+        assert_ne!(FontStyle::Oblique(Some(10.)), FontStyle::Oblique(None));
+    }
+
+    #[test]
+    fn test_generic_font_family_names() {
+        assert_eq!(
+            GenericFontFamily::from_name("monospace"),
+            Some(GenericFontFamily::Monospace)
+        );
+        assert_eq!(
+            GenericFontFamily::from_name("Sans-Serif"),
+            Some(GenericFontFamily::SansSerif)
+        );
+        assert_eq!(GenericFontFamily::from_name("Zed Plex Mono"), None);
+        assert_eq!(GenericFontFamily::from_name(".SystemUIFont"), None);
+    }
+
+    #[test]
+    fn test_layout_cache_hit_across_feature_orders() {
+        let text_system = test_text_system();
+        let shape = |features: FontFeatures| {
+            let run = TextRun {
+                font: Font {
+                    features,
+                    ..font(TestTextSystem::FONT_FAMILY)
+                },
+                ..run(5)
+            };
+            text_system
+                .shape_text("hello".into(), px(16.), &[run], WrapOptions::default())
+                .unwrap()
+        };
+
+        let liga_first: FontFeatures = serde_json::from_str(r#"{"liga": 0, "calt": 0}"#).unwrap();
+        let calt_first: FontFeatures = serde_json::from_str(r#"{"calt": 0, "liga": 0}"#).unwrap();
+        let reversed = FontFeatures::new(vec![("liga".into(), 0), ("calt".into(), 0)]);
+        shape(liga_first);
+        shape(calt_first);
+        shape(reversed);
+
+        // The first call misses in both the wrapped and the unwrapped line caches.
+        let stats = text_system.layout_cache_stats();
+        assert_eq!(stats.current_frame.misses, 2);
+        assert_eq!(stats.current_frame.hits, 2);
+        assert_eq!(text_system.font_ids_by_font.read().len(), 1);
+    }
+
+    #[test]
+    fn test_resolve_face_info() {
+        let text_system = test_text_system();
+        let test_font = font(TestTextSystem::FONT_FAMILY);
+
+        // The test font only has a regular face.
+        assert_eq!(
+            text_system.resolve_face_info(&test_font),
+            FaceInfo {
+                family_matched: true,
+                weight_matched: true,
+                synthetic_bold: false,
+                synthetic_italic: false,
+            }
+        );
+        assert_eq!(
+            text_system.resolve_face_info(&test_font.clone().bold().italic()),
+            FaceInfo {
+                family_matched: true,
+                weight_matched: false,
+                synthetic_bold: true,
+                synthetic_italic: true,
+            }
+        );
+
+        // A medium weight is shown with the regular face without being emboldened.
+        let medium = Font {
+            weight: FontWeight::MEDIUM,
+            ..test_font
+        };
+        assert_eq!(
+            text_system.resolve_face_info(&medium),
+            FaceInfo {
+                family_matched: true,
+                weight_matched: false,
+                synthetic_bold: false,
+                synthetic_italic: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_font_builders() {
+        let base = font("Zed Plex Mono")
+            .with_features(FontFeatures::default().calt(false).tnum(true))
+            .italic();
+        let derived = base
+            .clone()
+            .with_family("Zed Plex Sans")
+            .with_weight(FontWeight::SEMIBOLD)
+            .with_features(FontFeatures::default().tnum(false).small_caps(true));
+
+        assert_eq!(derived.family, "Zed Plex Sans");
+        assert_eq!(derived.weight, FontWeight::SEMIBOLD);
+        assert_eq!(derived.style, FontStyle::Italic);
+        assert_eq!(
+            derived.features,
+            FontFeatures::default()
+                .calt(false)
+                .tnum(false)
+                .small_caps(true)
+        );
+    }
+}
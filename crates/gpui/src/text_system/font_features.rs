@@ -1,17 +1,85 @@
 use std::sync::Arc;
 
-use schemars::schema::{InstanceType, SchemaObject};
+use schemars::schema::{InstanceType, SchemaObject, SingleOrVec};
 
 /// The OpenType features that can be configured for a given font.
+///
+/// The features are kept sorted by tag with at most one value per tag, so that equality and
+/// hashing don't depend on the order features were specified in. Values can only be built with
+/// [`FontFeatures::new`], the builder methods, or deserialization, which all preserve this.
 #[derive(Default, Clone, Eq, PartialEq, Hash)]
-pub struct FontFeatures(pub Arc<Vec<(String, u32)>>);
+pub struct FontFeatures(Arc<Vec<(String, u32)>>);
 
 impl FontFeatures {
+    /// Create a set of features from a list of tags and values. When a tag appears more than
+    /// once, its last value is used.
+    pub fn new(mut features: Vec<(String, u32)>) -> Self {
+        // Reversing before the stable sort puts the last value for each tag first.
+        features.reverse();
+        features.sort_by(|(a, _), (b, _)| a.cmp(b));
+        features.dedup_by(|(a, _), (b, _)| a == b);
+        FontFeatures(Arc::new(features))
+    }
+
     /// Get the tag name list of the font OpenType features
     /// only enabled or disabled features are returned
     pub fn tag_value_list(&self) -> &[(String, u32)] {
         &self.0.as_slice()
     }
+
+    /// Set an OpenType feature by its four-character tag, replacing any existing value for it.
+    /// A value of 0 disables the feature, 1 enables it, and higher values select alternates.
+    pub fn with_feature(mut self, tag: &str, value: u32) -> Self {
+        if !is_valid_feature_tag(tag) {
+            log::error!("Incorrect font feature tag: {}", tag);
+            return self;
+        }
+
+        let features = Arc::make_mut(&mut self.0);
+        match features.binary_search_by(|(existing, _)| existing.as_str().cmp(tag)) {
+            Ok(ix) => features[ix].1 = value,
+            Err(ix) => features.insert(ix, (tag.to_string(), value)),
+        }
+        self
+    }
+
+    /// Enable or disable standard and contextual ligatures (`liga` and `clig`).
+    pub fn ligatures(self, enabled: bool) -> Self {
+        self.with_feature("liga", enabled as u32)
+            .with_feature("clig", enabled as u32)
+    }
+
+    /// Enable or disable contextual alternates (`calt`).
+    pub fn calt(self, enabled: bool) -> Self {
+        self.with_feature("calt", enabled as u32)
+    }
+
+    /// Enable or disable tabular, fixed-width figures (`tnum`).
+    pub fn tnum(self, enabled: bool) -> Self {
+        self.with_feature("tnum", enabled as u32)
+    }
+
+    /// Enable or disable small capitals (`smcp`).
+    pub fn small_caps(self, enabled: bool) -> Self {
+        self.with_feature("smcp", enabled as u32)
+    }
+}
+
+impl std::fmt::Display for FontFeatures {
+    /// Lists enabled features by tag, disabled features as `-tag`, and alternates as `tag=value`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (ix, (tag, value)) in self.tag_value_list().iter().enumerate() {
+            if ix > 0 {
+                f.write_str(", ")?;
+            }
+            match value {
+                0 => write!(f, "-{}", tag)?,
+                1 => write!(f, "{}", tag)?,
+                _ => write!(f, "{}={}", tag, value)?,
+            }
+        }
+        Ok(())
+    }
 }
 
 impl std::fmt::Debug for FontFeatures {
@@ -37,7 +105,7 @@ impl<'de> serde::Deserialize<'de> for FontFeatures {
     where
         D: serde::Deserializer<'de>,
     {
-        use serde::de::{MapAccess, Visitor};
+        use serde::de::{MapAccess, SeqAccess, Visitor};
         use std::fmt;
 
         struct FontFeaturesVisitor;
@@ -46,7 +114,18 @@ impl<'de> serde::Deserialize<'de> for FontFeatures {
             type Value = FontFeatures;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("a map of font features")
+                formatter.write_str("a map of font features or a list of feature tags")
+            }
+
+            fn visit_seq<S>(self, mut access: S) -> Result<Self::Value, S::Error>
+            where
+                S: SeqAccess<'de>,
+            {
+                let mut features = FontFeatures::default();
+                while let Some(tag) = access.next_element::<String>()? {
+                    features = features.with_feature(&tag, 1);
+                }
+                Ok(features)
             }
 
             fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
@@ -87,11 +166,11 @@ impl<'de> serde::Deserialize<'de> for FontFeatures {
                     }
                 }
 
-                Ok(FontFeatures(Arc::new(feature_list)))
+                Ok(FontFeatures::new(feature_list))
             }
         }
 
-        let features = deserializer.deserialize_map(FontFeaturesVisitor)?;
+        let features = deserializer.deserialize_any(FontFeaturesVisitor)?;
         Ok(features)
     }
 }
@@ -119,13 +198,11 @@ impl schemars::JsonSchema for FontFeatures {
     }
 
     fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
-        let mut schema = SchemaObject::default();
-        schema.instance_type = Some(schemars::schema::SingleOrVec::Single(Box::new(
-            InstanceType::Object,
-        )));
+        let mut map_schema = SchemaObject::default();
+        map_schema.instance_type = Some(SingleOrVec::Single(Box::new(InstanceType::Object)));
         {
             let mut property = SchemaObject::default();
-            property.instance_type = Some(schemars::schema::SingleOrVec::Vec(vec![
+            property.instance_type = Some(SingleOrVec::Vec(vec![
                 InstanceType::Boolean,
                 InstanceType::Integer,
             ]));
@@ -134,11 +211,23 @@ impl schemars::JsonSchema for FontFeatures {
                 number_constraints.multiple_of = Some(1.0);
                 number_constraints.minimum = Some(0.0);
             }
-            schema
+            map_schema
                 .object()
                 .pattern_properties
                 .insert("[0-9a-zA-Z]{4}$".into(), property.into());
         }
+
+        let mut list_schema = SchemaObject::default();
+        list_schema.instance_type = Some(SingleOrVec::Single(Box::new(InstanceType::Array)));
+        {
+            let mut tag = SchemaObject::default();
+            tag.instance_type = Some(SingleOrVec::Single(Box::new(InstanceType::String)));
+            tag.string().pattern = Some("^[0-9a-zA-Z]{4}$".into());
+            list_schema.array().items = Some(SingleOrVec::Single(Box::new(tag.into())));
+        }
+
+        let mut schema = SchemaObject::default();
+        schema.subschemas().any_of = Some(vec![map_schema.into(), list_schema.into()]);
         schema.into()
     }
 }
@@ -146,3 +235,61 @@ impl schemars::JsonSchema for FontFeatures {
 fn is_valid_feature_tag(tag: &str) -> bool {
     tag.len() == 4 && tag.chars().all(|c| c.is_ascii_alphanumeric())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_builders() {
+        let features = FontFeatures::default()
+            .ligatures(false)
+            .tnum(true)
+            .with_feature("cv01", 2)
+            .calt(true)
+            .calt(false);
+        assert_eq!(
+            features.tag_value_list(),
+            &[
+                ("calt".to_string(), 0),
+                ("clig".to_string(), 0),
+                ("cv01".to_string(), 2),
+                ("liga".to_string(), 0),
+                ("tnum".to_string(), 1),
+            ]
+        );
+        assert_eq!(features.to_string(), "-calt, -clig, cv01=2, -liga, tnum");
+
+        let invalid = FontFeatures::default().with_feature("ligatures", 1);
+        assert!(invalid.tag_value_list().is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_feature_list() {
+        let from_list: FontFeatures = serde_json::from_str(r#"["tnum", "ss01"]"#).unwrap();
+        let from_map: FontFeatures = serde_json::from_str(r#"{"tnum": true, "ss01": 1}"#).unwrap();
+        assert_eq!(from_list, from_map);
+        assert_eq!(
+            from_list,
+            FontFeatures::default().tnum(true).with_feature("ss01", 1)
+        );
+    }
+
+    #[test]
+    fn test_feature_order_does_not_matter() {
+        let a: FontFeatures = serde_json::from_str(r#"{"liga": 0, "calt": 0}"#).unwrap();
+        let b: FontFeatures = serde_json::from_str(r#"{"calt": 0, "liga": 0}"#).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(
+            a,
+            FontFeatures::default().calt(false).with_feature("liga", 0)
+        );
+
+        let duplicated = FontFeatures::new(vec![
+            ("tnum".to_string(), 1),
+            ("calt".to_string(), 1),
+            ("tnum".to_string(), 0),
+        ]);
+        assert_eq!(duplicated, FontFeatures::default().tnum(false).calt(true));
+    }
+}
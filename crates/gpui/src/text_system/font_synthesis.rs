@@ -0,0 +1,273 @@
+use crate::{
+    point, size, Bounds, DevicePixels, FontStyle, FontWeight, Pixels, RenderGlyphParams, Size,
+};
+
+/// The weight from which a face counts as bold, as in CSS.
+const BOLD_WEIGHT: f32 = 600.;
+
+/// How far a synthesized italic slants, in degrees, unless an oblique style gives an angle.
+/// This is the angle FreeType uses.
+const OBLIQUE_ANGLE: f32 = 14.;
+
+/// Styles that are faked for a run of text because its font has no face with them, see
+/// [`crate::TextSystem::font_synthesis`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FontSynthesis {
+    /// How far each glyph is thickened to the right, to fake a bold face.
+    pub bold: Option<Pixels>,
+    /// How far each glyph is slanted, as a horizontal shift per unit of height above the
+    /// baseline, to fake an italic face.
+    pub oblique: Option<f32>,
+}
+
+impl FontSynthesis {
+    /// The styles that need faking to show a face with the given weight and style as the
+    /// requested ones, at the given font size.
+    pub(crate) fn for_face(
+        requested_weight: FontWeight,
+        requested_style: FontStyle,
+        face_weight: FontWeight,
+        face_style: FontStyle,
+        font_size: Pixels,
+    ) -> Self {
+        Self {
+            bold: (requested_weight.0 >= BOLD_WEIGHT && face_weight.0 < BOLD_WEIGHT)
+                // The strength FreeType uses to embolden.
+                .then(|| font_size / 24.),
+            oblique: (requested_style != FontStyle::Normal && face_style == FontStyle::Normal)
+                .then(|| {
+                    let angle = match requested_style {
+                        FontStyle::Oblique(Some(angle)) => angle,
+                        _ => OBLIQUE_ANGLE,
+                    };
+                    angle.to_radians().tan()
+                }),
+        }
+    }
+}
+
+/// How the face that a font resolves to compares to the font, see
+/// [`crate::TextSystem::resolve_face_info`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FaceInfo {
+    /// Whether the font's family was found. If not, a fallback family is used.
+    pub family_matched: bool,
+    /// Whether the face has exactly the requested weight.
+    pub weight_matched: bool,
+    /// Whether the face is emboldened because bold was requested and it isn't bold.
+    pub synthetic_bold: bool,
+    /// Whether the face is slanted because italic or oblique was requested and it is upright.
+    pub synthetic_italic: bool,
+}
+
+/// The raster bounds of a glyph with the synthesis in `params` applied, given the raster bounds
+/// of the glyph without it.
+pub(crate) fn synthesized_raster_bounds(
+    bounds: Bounds<DevicePixels>,
+    params: &RenderGlyphParams,
+) -> Bounds<DevicePixels> {
+    if bounds.size.width.0 == 0 || bounds.size.height.0 == 0 {
+        return bounds;
+    }
+
+    let (min_shift, max_shift) = oblique_shifts(
+        bounds.origin.y.0,
+        bounds.size.height.0,
+        params.synthetic_oblique,
+    );
+    Bounds {
+        origin: point(bounds.origin.x + DevicePixels(min_shift), bounds.origin.y),
+        size: size(
+            bounds.size.width + DevicePixels(max_shift - min_shift + bold_pixels(params)),
+            bounds.size.height,
+        ),
+    }
+}
+
+/// Applies the synthesis in `params` to a glyph's coverage mask, which was rasterized without
+/// it. `top` is the offset of the mask's first row from the baseline. Emboldening smears each
+/// row to the right, and slanting shifts each row in proportion to its height.
+pub(crate) fn synthesize_raster(
+    top: DevicePixels,
+    raster_size: Size<DevicePixels>,
+    raster: Vec<u8>,
+    params: &RenderGlyphParams,
+) -> (Size<DevicePixels>, Vec<u8>) {
+    let bold = bold_pixels(params) as usize;
+    let width = raster_size.width.0 as usize;
+    let height = raster_size.height.0 as usize;
+    if params.is_emoji
+        || width == 0
+        || height == 0
+        || (bold == 0 && params.synthetic_oblique.is_none())
+    {
+        return (raster_size, raster);
+    }
+
+    let (min_shift, max_shift) = oblique_shifts(top.0, height as i32, params.synthetic_oblique);
+    let synthesized_width = width + (max_shift - min_shift) as usize + bold;
+    let mut synthesized = vec![0; synthesized_width * height];
+    for (row, (source, target)) in raster
+        .chunks_exact(width)
+        .zip(synthesized.chunks_exact_mut(synthesized_width))
+        .enumerate()
+    {
+        let offset =
+            (oblique_shift(top.0 + row as i32, params.synthetic_oblique) - min_shift) as usize;
+        for (column, &coverage) in source.iter().enumerate() {
+            let start = offset + column;
+            for pixel in &mut target[start..=start + bold] {
+                *pixel = (*pixel).max(coverage);
+            }
+        }
+    }
+
+    (
+        size(DevicePixels(synthesized_width as i32), raster_size.height),
+        synthesized,
+    )
+}
+
+fn bold_pixels(params: &RenderGlyphParams) -> i32 {
+    params.synthetic_bold.map_or(0, |strength| {
+        (strength.0 * params.scale_factor).round().max(1.) as i32
+    })
+}
+
+/// How far the given row of a raster is shifted to slant it, where `y` is the row's offset from
+/// the baseline, which is negative above it.
+fn oblique_shift(y: i32, skew: Option<f32>) -> i32 {
+    skew.map_or(0, |skew| (-(y as f32 + 0.5) * skew).round() as i32)
+}
+
+/// The smallest and largest shifts of the rows of a raster, see [`oblique_shift`].
+fn oblique_shifts(top: i32, height: i32, skew: Option<f32>) -> (i32, i32) {
+    if height == 0 {
+        return (0, 0);
+    }
+    let top_shift = oblique_shift(top, skew);
+    let bottom_shift = oblique_shift(top + height - 1, skew);
+    (top_shift.min(bottom_shift), top_shift.max(bottom_shift))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{px, FontId, GlyphId, Point, TextRenderingMode};
+
+    fn params(synthesis: FontSynthesis) -> RenderGlyphParams {
+        RenderGlyphParams {
+            font_id: FontId(0),
+            glyph_id: GlyphId(0),
+            font_size: px(16.),
+            subpixel_variant: Point::default(),
+            scale_factor: 1.,
+            is_emoji: false,
+            rendering_mode: TextRenderingMode::Subpixel,
+            synthetic_bold: synthesis.bold,
+            synthetic_oblique: synthesis.oblique,
+        }
+    }
+
+    #[test]
+    fn test_font_synthesis_for_face() {
+        let regular = FontSynthesis::for_face(
+            FontWeight::BOLD,
+            FontStyle::Italic,
+            FontWeight::NORMAL,
+            FontStyle::Normal,
+            px(24.),
+        );
+        assert_eq!(regular.bold, Some(px(1.)));
+        assert!(regular.oblique.is_some());
+
+        let bold_italic = FontSynthesis::for_face(
+            FontWeight::BOLD,
+            FontStyle::Italic,
+            FontWeight::SEMIBOLD,
+            FontStyle::Oblique(None),
+            px(24.),
+        );
+        assert_eq!(bold_italic, FontSynthesis::default());
+    }
+
+    #[test]
+    fn test_font_synthesis_uses_oblique_angle() {
+        let synthesis = |style| {
+            FontSynthesis::for_face(
+                FontWeight::NORMAL,
+                style,
+                FontWeight::NORMAL,
+                FontStyle::Normal,
+                px(16.),
+            )
+            .oblique
+        };
+        assert_eq!(
+            synthesis(FontStyle::Oblique(None)),
+            synthesis(FontStyle::Italic)
+        );
+        assert_eq!(
+            synthesis(FontStyle::Oblique(Some(45.))),
+            Some(45f32.to_radians().tan())
+        );
+        assert_eq!(synthesis(FontStyle::Normal), None);
+    }
+
+    #[test]
+    fn test_synthetic_bold_raster() {
+        let params = params(FontSynthesis {
+            bold: Some(px(2.)),
+            oblique: None,
+        });
+        let bounds = Bounds::new(
+            point(DevicePixels(1), DevicePixels(-2)),
+            size(DevicePixels(2), DevicePixels(2)),
+        );
+        let synthesized_bounds = synthesized_raster_bounds(bounds, &params);
+        assert_eq!(synthesized_bounds.origin, bounds.origin);
+        assert_eq!(synthesized_bounds.size.width, DevicePixels(4));
+
+        let (raster_size, raster) =
+            synthesize_raster(bounds.origin.y, bounds.size, vec![255, 0, 0, 128], &params);
+        assert_eq!(raster_size, synthesized_bounds.size);
+        assert_eq!(raster, [255, 255, 255, 0, 0, 128, 128, 128]);
+    }
+
+    #[test]
+    fn test_synthetic_oblique_raster() {
+        let params = params(FontSynthesis {
+            bold: None,
+            oblique: Some(0.8),
+        });
+        // A vertical bar standing on the baseline.
+        let bounds = Bounds::new(
+            point(DevicePixels(0), DevicePixels(-3)),
+            size(DevicePixels(1), DevicePixels(3)),
+        );
+        let synthesized_bounds = synthesized_raster_bounds(bounds, &params);
+        assert_eq!(synthesized_bounds.origin.x, DevicePixels(0));
+        assert_eq!(synthesized_bounds.size.width, DevicePixels(3));
+
+        let (raster_size, raster) =
+            synthesize_raster(bounds.origin.y, bounds.size, vec![255; 3], &params);
+        assert_eq!(raster_size, synthesized_bounds.size);
+        #[rustfmt::skip]
+        let expected = [
+            0, 0, 255,
+            0, 255, 0,
+            255, 0, 0,
+        ];
+        assert_eq!(raster, expected);
+    }
+
+    #[test]
+    fn test_synthesis_skips_empty_glyphs() {
+        let params = params(FontSynthesis {
+            bold: Some(px(2.)),
+            oblique: Some(0.25),
+        });
+        let bounds = Bounds::default();
+        assert_eq!(synthesized_raster_bounds(bounds, &params), bounds);
+    }
+}
@@ -1,10 +1,15 @@
 use crate::{
-    black, fill, point, px, size, Bounds, Hsla, LineLayout, Pixels, Point, Result, SharedString,
-    StrikethroughStyle, UnderlineStyle, WindowContext, WrapBoundary, WrappedLineLayout,
+    black, fill, point, px, size, Bounds, Corners, FontId, FontStyle, FontSynthesis, FontWeight,
+    GlyphId, Hsla, LineLayout, Path, Pixels, Point, Result, SharedString, StrikethroughStyle,
+    TextSystem, UnderlineStyle, WindowContext, WrapBoundary, WrappedLineLayout,
 };
 use derive_more::{Deref, DerefMut};
 use smallvec::SmallVec;
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// U+00AD, which marks where a word may be hyphenated if a line needs to be wrapped there.
+pub(crate) const SOFT_HYPHEN: char = '\u{ad}';
 
 /// Set the text decoration for a run of text.
 #[derive(Debug, Clone)]
@@ -15,6 +20,14 @@ pub struct DecorationRun {
     /// The color for this run
     pub color: Hsla,
 
+    /// The font weight requested for this run, which is synthesized if the font has no face
+    /// with it.
+    pub font_weight: FontWeight,
+
+    /// The font style requested for this run, which is synthesized if the font has no face
+    /// with it.
+    pub font_style: FontStyle,
+
     /// The background color for this run
     pub background_color: Option<Hsla>,
 
@@ -25,6 +38,47 @@ pub struct DecorationRun {
     pub strikethrough: Option<StrikethroughStyle>,
 }
 
+/// How whitespace is made visible when a line is painted. The shaped text is unaffected, so
+/// this doesn't change hit testing or wrapping.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvisibleStyle {
+    /// The character painted in place of a space.
+    pub space: char,
+    /// The character painted in place of a tab.
+    pub tab: char,
+    /// The color of the substituted characters.
+    pub color: Hsla,
+    /// The color of substituted characters in trailing whitespace, if it should stand out.
+    pub trailing_color: Option<Hsla>,
+}
+
+impl InvisibleStyle {
+    /// Paint spaces as "·" and tabs as "→" in the given color.
+    pub fn new(color: Hsla) -> Self {
+        Self {
+            space: '·',
+            tab: '→',
+            color,
+            trailing_color: None,
+        }
+    }
+
+    /// The character and color to paint in place of the given character, if it is whitespace.
+    fn substitute(&self, ch: char, is_trailing: bool) -> Option<(char, Hsla)> {
+        let substitute = match ch {
+            ' ' => self.space,
+            '\t' => self.tab,
+            _ => return None,
+        };
+        let color = if is_trailing {
+            self.trailing_color.unwrap_or(self.color)
+        } else {
+            self.color
+        };
+        Some((substitute, color))
+    }
+}
+
 /// A line of text that has been shaped and decorated.
 #[derive(Clone, Default, Debug, Deref, DerefMut)]
 pub struct ShapedLine {
@@ -34,6 +88,9 @@ pub struct ShapedLine {
     /// The text that was shaped for this line.
     pub text: SharedString,
     pub(crate) decoration_runs: SmallVec<[DecorationRun; 32]>,
+    pub(crate) invisibles: Option<InvisibleStyle>,
+    pub(crate) background_corner_radius: Pixels,
+    pub(crate) marked_range: Option<(Range<usize>, UnderlineStyle)>,
 }
 
 impl ShapedLine {
@@ -43,6 +100,47 @@ impl ShapedLine {
         self.layout.len
     }
 
+    /// The byte range of the Unicode (UAX #29) word segment containing the given index, e.g. for
+    /// selecting a word on double-click. An index at the end of the text is treated as being in
+    /// the last segment.
+    pub fn word_range_at(&self, index: usize) -> Range<usize> {
+        word_range_at(&self.text, index)
+    }
+
+    /// The end of the first word that ends after the given index, or the length of the text if
+    /// there is none. Whitespace and punctuation are skipped over.
+    pub fn next_word_boundary(&self, index: usize) -> usize {
+        next_word_boundary(&self.text, index)
+    }
+
+    /// The start of the last word that starts before the given index, or 0 if there is none.
+    /// Whitespace and punctuation are skipped over.
+    pub fn prev_word_boundary(&self, index: usize) -> usize {
+        prev_word_boundary(&self.text, index)
+    }
+
+    /// Paint whitespace in this line with the given style.
+    pub fn with_invisibles(mut self, invisibles: InvisibleStyle) -> Self {
+        self.invisibles = Some(invisibles);
+        self
+    }
+
+    /// Round the corners of run backgrounds by the given radius. Where a background is split
+    /// by a wrap, the corners at the split stay square.
+    pub fn with_background_corner_radius(mut self, radius: Pixels) -> Self {
+        self.background_corner_radius = radius;
+        self
+    }
+
+    /// Underline the given byte range, e.g. the text an input method is composing. This is
+    /// painted over the line's own decorations and doesn't change its layout. The underline's
+    /// color defaults to the color of the text at the start of the range, and its thickness
+    /// to the font's underline thickness.
+    pub fn with_marked_range(mut self, range: Range<usize>, style: UnderlineStyle) -> Self {
+        self.marked_range = Some((range, style));
+        self
+    }
+
     /// Paint the line of text to the window.
     pub fn paint(
         &self,
@@ -50,17 +148,85 @@ impl ShapedLine {
         line_height: Pixels,
         cx: &mut WindowContext,
     ) -> Result<()> {
-        paint_line(
-            origin,
-            &self.layout,
-            line_height,
-            &self.decoration_runs,
-            &[],
-            cx,
-        )?;
+        paint_line(&self.view(), origin, line_height, cx)?;
+
+        if let Some((range, style)) = &self.marked_range {
+            paint_marked_range(
+                &self.layout,
+                &self.decoration_runs,
+                range.start,
+                [self.bounds_for_range(range.clone(), origin, line_height)],
+                style,
+                cx,
+            );
+        }
 
         Ok(())
     }
+
+    /// The bounds of the given byte range when the line is painted at `origin`, e.g. for
+    /// positioning an input method's candidate window. An empty range gives a zero-width
+    /// rectangle at the caret.
+    pub fn bounds_for_range(
+        &self,
+        range: Range<usize>,
+        origin: Point<Pixels>,
+        line_height: Pixels,
+    ) -> Bounds<Pixels> {
+        let start = range.start.min(self.len());
+        let end = range.end.clamp(start, self.len());
+        let start_x = self.layout.x_for_index(start);
+        let end_x = self.layout.x_for_index(end);
+        Bounds::new(
+            point(origin.x + start_x, origin.y),
+            size(end_x - start_x, line_height),
+        )
+    }
+
+    /// The vector outlines of the glyphs in this line, positioned as [`Self::paint`] would paint
+    /// them at `origin`, for uses where rasterized glyphs won't do, such as SVG export.
+    /// Backgrounds and decorations aren't included. Fails if a glyph that isn't whitespace has
+    /// no outline, see [`TextSystem::glyph_outline`], and always fails on Windows, where outlines
+    /// aren't supported yet.
+    pub fn to_paths(
+        &self,
+        origin: Point<Pixels>,
+        line_height: Pixels,
+        text_system: &TextSystem,
+    ) -> Result<Vec<Path<Pixels>>> {
+        outline_line(&self.view(), origin, line_height, text_system)
+    }
+
+    /// Paint a background behind the given byte range of the line, e.g. for a selection, with
+    /// its corners rounded by `corner_radius`. This should be called before [`Self::paint`] so
+    /// that the glyphs are drawn on top.
+    pub fn paint_selection(
+        &self,
+        origin: Point<Pixels>,
+        line_height: Pixels,
+        range: Range<usize>,
+        color: Hsla,
+        corner_radius: Pixels,
+        cx: &mut WindowContext,
+    ) {
+        let bounds = self.bounds_for_range(range, origin, line_height);
+        if bounds.size.width <= px(0.) {
+            return;
+        }
+        cx.paint_quad(fill(bounds, color).corner_radii(corner_radius));
+    }
+
+    fn view(&self) -> LineView {
+        LineView {
+            layout: &self.layout,
+            text: &self.text,
+            decoration_runs: &self.decoration_runs,
+            invisibles: self.invisibles.as_ref(),
+            background_corner_radius: self.background_corner_radius,
+            wrap_boundaries: &[],
+            wrap_indent: px(0.),
+        }
+    }
 }
 
 /// A line of text that has been shaped, decorated, and wrapped by the text layout system.
@@ -72,6 +238,9 @@ pub struct WrappedLine {
     /// The text that was shaped for this line.
     pub text: SharedString,
     pub(crate) decoration_runs: SmallVec<[DecorationRun; 32]>,
+    pub(crate) invisibles: Option<InvisibleStyle>,
+    pub(crate) background_corner_radius: Pixels,
+    pub(crate) marked_range: Option<(Range<usize>, UnderlineStyle)>,
 }
 
 impl WrappedLine {
@@ -81,6 +250,45 @@ impl WrappedLine {
         self.layout.len()
     }
 
+    /// The byte range of the Unicode (UAX #29) word segment containing the given index, e.g. for
+    /// selecting a word on double-click. An index at the end of the text is treated as being in
+    /// the last segment.
+    pub fn word_range_at(&self, index: usize) -> Range<usize> {
+        word_range_at(&self.text, index)
+    }
+
+    /// The end of the first word that ends after the given index, or the length of the text if
+    /// there is none. Whitespace and punctuation are skipped over.
+    pub fn next_word_boundary(&self, index: usize) -> usize {
+        next_word_boundary(&self.text, index)
+    }
+
+    /// The start of the last word that starts before the given index, or 0 if there is none.
+    /// Whitespace and punctuation are skipped over.
+    pub fn prev_word_boundary(&self, index: usize) -> usize {
+        prev_word_boundary(&self.text, index)
+    }
+
+    /// Paint whitespace in this line with the given style.
+    pub fn with_invisibles(mut self, invisibles: InvisibleStyle) -> Self {
+        self.invisibles = Some(invisibles);
+        self
+    }
+
+    /// Round the corners of run backgrounds by the given radius. Where a background is split
+    /// by a wrap, the corners at the split stay square.
+    pub fn with_background_corner_radius(mut self, radius: Pixels) -> Self {
+        self.background_corner_radius = radius;
+        self
+    }
+
+    /// Underline the given byte range on each visual line it spans, see
+    /// [`ShapedLine::with_marked_range`].
+    pub fn with_marked_range(mut self, range: Range<usize>, style: UnderlineStyle) -> Self {
+        self.marked_range = Some((range, style));
+        self
+    }
+
     /// Paint this line of text to the window.
     pub fn paint(
         &self,
@@ -88,27 +296,221 @@ impl WrappedLine {
         line_height: Pixels,
         cx: &mut WindowContext,
     ) -> Result<()> {
-        paint_line(
-            origin,
-            &self.layout.unwrapped_layout,
-            line_height,
-            &self.decoration_runs,
-            &self.wrap_boundaries,
-            cx,
-        )?;
+        paint_line(&self.view(), origin, line_height, cx)?;
+
+        if let Some((range, style)) = &self.marked_range {
+            let line_bounds = self.layout.bounds_for_range(range.clone(), line_height);
+            paint_marked_range(
+                &self.layout.unwrapped_layout,
+                &self.decoration_runs,
+                range.start,
+                line_bounds
+                    .into_iter()
+                    .map(|bounds| Bounds::new(origin + bounds.origin, bounds.size)),
+                style,
+                cx,
+            );
+        }
 
         Ok(())
     }
+
+    /// The bounds of the part of the given byte range on the first visual line it spans, when
+    /// the line is painted at `origin`. This is what platforms ask for to position an input
+    /// method's candidate window. An empty range gives a zero-width rectangle at the caret.
+    pub fn first_bounds_for_range(
+        &self,
+        range: Range<usize>,
+        origin: Point<Pixels>,
+        line_height: Pixels,
+    ) -> Option<Bounds<Pixels>> {
+        if range.is_empty() {
+            let position = self.layout.position_for_index(range.start, line_height)?;
+            return Some(Bounds::new(origin + position, size(px(0.), line_height)));
+        }
+
+        let bounds = *self.layout.bounds_for_range(range, line_height).first()?;
+        Some(Bounds::new(origin + bounds.origin, bounds.size))
+    }
+
+    /// The vector outlines of the glyphs in this line, positioned as [`Self::paint`] would paint
+    /// them at `origin`, with one path per glyph. See [`ShapedLine::to_paths`].
+    pub fn to_paths(
+        &self,
+        origin: Point<Pixels>,
+        line_height: Pixels,
+        text_system: &TextSystem,
+    ) -> Result<Vec<Path<Pixels>>> {
+        outline_line(&self.view(), origin, line_height, text_system)
+    }
+
+    /// Paint a background behind the given byte range of the line, e.g. for a selection.
+    /// One quad is painted for each visual line the range spans. Only the outer corners of
+    /// the whole region are rounded by `corner_radius`, so that it reads as a single shape.
+    /// This should be called before [`Self::paint`] so that the glyphs are drawn on top.
+    pub fn paint_selection(
+        &self,
+        origin: Point<Pixels>,
+        line_height: Pixels,
+        range: Range<usize>,
+        color: Hsla,
+        corner_radius: Pixels,
+        cx: &mut WindowContext,
+    ) {
+        let line_bounds = self.layout.bounds_for_range(range, line_height);
+        let last_ix = line_bounds.len().saturating_sub(1);
+        for (ix, bounds) in line_bounds.into_iter().enumerate() {
+            cx.paint_quad(
+                fill(Bounds::new(origin + bounds.origin, bounds.size), color)
+                    .corner_radii(selection_corners(corner_radius, ix == 0, ix == last_ix)),
+            );
+        }
+    }
+
+    fn view(&self) -> LineView {
+        LineView {
+            layout: &self.layout.unwrapped_layout,
+            text: &self.text,
+            decoration_runs: &self.decoration_runs,
+            invisibles: self.invisibles.as_ref(),
+            background_corner_radius: self.background_corner_radius,
+            wrap_boundaries: &self.wrap_boundaries,
+            wrap_indent: self.wrap_indent,
+        }
+    }
+}
+
+/// What [`paint_line`] and [`outline_line`] need from a [`ShapedLine`] or [`WrappedLine`]. An
+/// unwrapped line has no wrap boundaries.
+#[derive(Clone, Copy)]
+struct LineView<'a> {
+    layout: &'a LineLayout,
+    text: &'a str,
+    decoration_runs: &'a [DecorationRun],
+    invisibles: Option<&'a InvisibleStyle>,
+    background_corner_radius: Pixels,
+    wrap_boundaries: &'a [WrapBoundary],
+    wrap_indent: Pixels,
+}
+
+/// Underline each visual line's part of a marked range, see [`ShapedLine::with_marked_range`].
+/// The underline sits where [`paint_line`] puts run underlines.
+fn paint_marked_range(
+    layout: &LineLayout,
+    decoration_runs: &[DecorationRun],
+    start: usize,
+    line_bounds: impl IntoIterator<Item = Bounds<Pixels>>,
+    style: &UnderlineStyle,
+    cx: &mut WindowContext,
+) {
+    let color = style.color.or_else(|| {
+        let mut run_end = 0;
+        decoration_runs
+            .iter()
+            .find(|run| {
+                run_end += run.len as usize;
+                run_end > start
+            })
+            .or(decoration_runs.last())
+            .map(|run| run.color)
+    });
+    let thickness = if style.thickness == px(0.) {
+        layout.font_id_for_index(start).map_or(px(1.), |font_id| {
+            cx.text_system()
+                .underline_thickness(font_id, layout.font_size)
+        })
+    } else {
+        style.thickness
+    };
+    let style = UnderlineStyle {
+        color,
+        thickness,
+        wavy: style.wavy,
+    };
+
+    for bounds in line_bounds {
+        if bounds.size.width <= px(0.) {
+            continue;
+        }
+        let padding_top = (bounds.size.height - layout.ascent - layout.descent) / 2.;
+        let underline_origin = point(
+            bounds.left(),
+            bounds.top() + padding_top + layout.ascent + (layout.descent * 0.618),
+        );
+        cx.paint_underline(underline_origin, bounds.size.width, &style);
+    }
+}
+
+/// The corners of a visual line's part of a selection. The top corners are only rounded on
+/// the first line, and the bottom corners on the last, so that the joins stay square.
+fn selection_corners(radius: Pixels, is_first: bool, is_last: bool) -> Corners<Pixels> {
+    let top = if is_first { radius } else { px(0.) };
+    let bottom = if is_last { radius } else { px(0.) };
+    Corners {
+        top_left: top,
+        top_right: top,
+        bottom_right: bottom,
+        bottom_left: bottom,
+    }
+}
+
+/// The corners of a run background that may have been split by a wrap, with square corners
+/// on the sides where it was split.
+fn background_corners(radius: Pixels, round_left: bool, round_right: bool) -> Corners<Pixels> {
+    let left = if round_left { radius } else { px(0.) };
+    let right = if round_right { radius } else { px(0.) };
+    Corners {
+        top_left: left,
+        top_right: right,
+        bottom_right: right,
+        bottom_left: left,
+    }
+}
+
+fn word_range_at(text: &str, index: usize) -> Range<usize> {
+    text.split_word_bound_indices()
+        .map(|(start, segment)| start..start + segment.len())
+        .take_while(|range| range.start <= index)
+        .last()
+        .unwrap_or(index..index)
+}
+
+fn next_word_boundary(text: &str, index: usize) -> usize {
+    text.split_word_bound_indices()
+        .filter(|(_, segment)| is_word(segment))
+        .map(|(start, segment)| start + segment.len())
+        .find(|&end| end > index)
+        .unwrap_or(text.len())
+}
+
+fn prev_word_boundary(text: &str, index: usize) -> usize {
+    text.split_word_bound_indices()
+        .filter(|(_, segment)| is_word(segment))
+        .map(|(start, _)| start)
+        .take_while(|&start| start < index)
+        .last()
+        .unwrap_or(0)
+}
+
+fn is_word(segment: &str) -> bool {
+    segment.chars().any(|c| c.is_alphanumeric() || c == '_')
 }
 
 fn paint_line(
+    line: &LineView,
     origin: Point<Pixels>,
-    layout: &LineLayout,
     line_height: Pixels,
-    decoration_runs: &[DecorationRun],
-    wrap_boundaries: &[WrapBoundary],
     cx: &mut WindowContext,
 ) -> Result<()> {
+    let LineView {
+        layout,
+        text,
+        decoration_runs,
+        invisibles,
+        background_corner_radius,
+        wrap_boundaries,
+        wrap_indent,
+    } = *line;
     let line_bounds = Bounds::new(origin, size(layout.width, line_height));
     cx.paint_layer(line_bounds, |cx| {
         let padding_top = (line_height - layout.ascent - layout.descent) / 2.;
@@ -117,12 +519,25 @@ fn paint_line(
         let mut wraps = wrap_boundaries.iter().peekable();
         let mut run_end = 0;
         let mut color = black();
+        let mut font_weight = FontWeight::default();
+        let mut font_style = FontStyle::default();
+        // The font, weight and style the last glyph's synthesis was looked up for.
+        let mut synthesis_key: Option<(FontId, FontWeight, FontStyle)> = None;
+        let mut synthesis = FontSynthesis::default();
         let mut current_underline: Option<(Point<Pixels>, UnderlineStyle)> = None;
         let mut current_strikethrough: Option<(Point<Pixels>, StrikethroughStyle)> = None;
-        let mut current_background: Option<(Point<Pixels>, Hsla)> = None;
+        // The start of the background, its color, and whether its left corners are rounded,
+        // which they aren't when it continues from the previous visual line.
+        let mut current_background: Option<(Point<Pixels>, Hsla, bool)> = None;
         let text_system = cx.text_system().clone();
+        let trailing_whitespace_start = text.trim_end().len();
+        // Glyphs that share a font, color and synthesis are painted together, see
+        // `WindowContext::paint_glyph_run`.
+        let mut glyph_batch = SmallVec::<[(GlyphId, Point<Pixels>); 64]>::new();
+        let mut glyph_batch_key: Option<(FontId, Hsla, FontSynthesis)> = None;
         let mut glyph_origin = origin;
         let mut prev_glyph_position = Point::default();
+        let mut prev_glyph_font_id = None;
         for (run_ix, run) in layout.runs.iter().enumerate() {
             let max_glyph_size = text_system.bounding_box(run.font_id, layout.font_size).size;
 
@@ -131,17 +546,45 @@ fn paint_line(
 
                 if wraps.peek() == Some(&&WrapBoundary { run_ix, glyph_ix }) {
                     wraps.next();
-                    if let Some((background_origin, background_color)) = current_background.as_mut()
+                    // Soft hyphens are only visible when the line is wrapped at them. The
+                    // hyphen is styled like the glyph before it.
+                    if let Some(font_id) =
+                        prev_glyph_font_id.filter(|_| text[..glyph.index].ends_with(SOFT_HYPHEN))
                     {
-                        cx.paint_quad(fill(
-                            Bounds {
-                                origin: *background_origin,
-                                size: size(glyph_origin.x - background_origin.x, line_height),
-                            },
-                            *background_color,
-                        ));
-                        background_origin.x = origin.x;
+                        if let Some(hyphen) = text_system
+                            .platform_text_system
+                            .glyph_for_char(font_id, '-')
+                        {
+                            cx.paint_glyph(
+                                glyph_origin + baseline_offset,
+                                font_id,
+                                hyphen,
+                                layout.font_size,
+                                synthesis,
+                                color,
+                            )?;
+                        }
+                    }
+                    if let Some((background_origin, background_color, rounds_left)) =
+                        current_background.as_mut()
+                    {
+                        cx.paint_quad(
+                            fill(
+                                Bounds {
+                                    origin: *background_origin,
+                                    size: size(glyph_origin.x - background_origin.x, line_height),
+                                },
+                                *background_color,
+                            )
+                            .corner_radii(background_corners(
+                                background_corner_radius,
+                                *rounds_left,
+                                false,
+                            )),
+                        );
+                        background_origin.x = origin.x + wrap_indent;
                         background_origin.y += line_height;
+                        *rounds_left = false;
                     }
                     if let Some((underline_origin, underline_style)) = current_underline.as_mut() {
                         cx.paint_underline(
@@ -149,7 +592,7 @@ fn paint_line(
                             glyph_origin.x - underline_origin.x,
                             underline_style,
                         );
-                        underline_origin.x = origin.x;
+                        underline_origin.x = origin.x + wrap_indent;
                         underline_origin.y += line_height;
                     }
                     if let Some((strikethrough_origin, strikethrough_style)) =
@@ -160,21 +603,22 @@ fn paint_line(
                             glyph_origin.x - strikethrough_origin.x,
                             strikethrough_style,
                         );
-                        strikethrough_origin.x = origin.x;
+                        strikethrough_origin.x = origin.x + wrap_indent;
                         strikethrough_origin.y += line_height;
                     }
 
-                    glyph_origin.x = origin.x;
+                    glyph_origin.x = origin.x + wrap_indent;
                     glyph_origin.y += line_height;
                 }
                 prev_glyph_position = glyph.position;
+                prev_glyph_font_id = Some(run.font_id);
 
-                let mut finished_background: Option<(Point<Pixels>, Hsla)> = None;
+                let mut finished_background: Option<(Point<Pixels>, Hsla, bool)> = None;
                 let mut finished_underline: Option<(Point<Pixels>, UnderlineStyle)> = None;
                 let mut finished_strikethrough: Option<(Point<Pixels>, StrikethroughStyle)> = None;
                 if glyph.index >= run_end {
                     if let Some(style_run) = decoration_runs.next() {
-                        if let Some((_, background_color)) = &mut current_background {
+                        if let Some((_, background_color, _)) = &mut current_background {
                             if style_run.background_color.as_ref() != Some(background_color) {
                                 finished_background = current_background.take();
                             }
@@ -183,6 +627,7 @@ fn paint_line(
                             current_background.get_or_insert((
                                 point(glyph_origin.x, glyph_origin.y),
                                 run_background,
+                                true,
                             ));
                         }
 
@@ -199,7 +644,12 @@ fn paint_line(
                                 ),
                                 UnderlineStyle {
                                     color: Some(run_underline.color.unwrap_or(style_run.color)),
-                                    thickness: run_underline.thickness,
+                                    thickness: if run_underline.thickness == px(0.) {
+                                        text_system
+                                            .underline_thickness(run.font_id, layout.font_size)
+                                    } else {
+                                        run_underline.thickness
+                                    },
                                     wavy: run_underline.wavy,
                                 },
                             ));
@@ -218,13 +668,20 @@ fn paint_line(
                                 ),
                                 StrikethroughStyle {
                                     color: Some(run_strikethrough.color.unwrap_or(style_run.color)),
-                                    thickness: run_strikethrough.thickness,
+                                    thickness: if run_strikethrough.thickness == px(0.) {
+                                        text_system
+                                            .underline_thickness(run.font_id, layout.font_size)
+                                    } else {
+                                        run_strikethrough.thickness
+                                    },
                                 },
                             ));
                         }
 
                         run_end += style_run.len as usize;
                         color = style_run.color;
+                        font_weight = style_run.font_weight;
+                        font_style = style_run.font_style;
                     } else {
                         run_end = layout.len;
                         finished_background = current_background.take();
@@ -233,14 +690,23 @@ fn paint_line(
                     }
                 }
 
-                if let Some((background_origin, background_color)) = finished_background {
-                    cx.paint_quad(fill(
-                        Bounds {
-                            origin: background_origin,
-                            size: size(glyph_origin.x - background_origin.x, line_height),
-                        },
-                        background_color,
-                    ));
+                if let Some((background_origin, background_color, rounds_left)) =
+                    finished_background
+                {
+                    cx.paint_quad(
+                        fill(
+                            Bounds {
+                                origin: background_origin,
+                                size: size(glyph_origin.x - background_origin.x, line_height),
+                            },
+                            background_color,
+                        )
+                        .corner_radii(background_corners(
+                            background_corner_radius,
+                            rounds_left,
+                            true,
+                        )),
+                    );
                 }
 
                 if let Some((underline_origin, underline_style)) = finished_underline {
@@ -259,14 +725,44 @@ fn paint_line(
                     );
                 }
 
+                if synthesis_key != Some((run.font_id, font_weight, font_style)) {
+                    synthesis = text_system.font_synthesis(
+                        run.font_id,
+                        font_weight,
+                        font_style,
+                        layout.font_size,
+                    );
+                    synthesis_key = Some((run.font_id, font_weight, font_style));
+                }
+
                 let max_glyph_bounds = Bounds {
                     origin: glyph_origin,
                     size: max_glyph_size,
                 };
 
                 let content_mask = cx.content_mask();
-                if max_glyph_bounds.intersects(&content_mask.bounds) {
-                    if glyph.is_emoji {
+                if max_glyph_bounds.intersects(&content_mask.bounds)
+                    && !text[glyph.index..].starts_with(SOFT_HYPHEN)
+                {
+                    let invisible = invisibles.and_then(|invisibles| {
+                        let ch = text[glyph.index..].chars().next()?;
+                        invisibles.substitute(ch, glyph.index >= trailing_whitespace_start)
+                    });
+                    if let Some((substitute, invisible_color)) = invisible {
+                        if let Some(substitute) = text_system
+                            .platform_text_system
+                            .glyph_for_char(run.font_id, substitute)
+                        {
+                            cx.paint_glyph(
+                                glyph_origin + baseline_offset,
+                                run.font_id,
+                                substitute,
+                                layout.font_size,
+                                synthesis,
+                                invisible_color,
+                            )?;
+                        }
+                    } else if glyph.is_emoji {
                         cx.paint_emoji(
                             glyph_origin + baseline_offset,
                             run.font_id,
@@ -274,33 +770,60 @@ fn paint_line(
                             layout.font_size,
                         )?;
                     } else {
-                        cx.paint_glyph(
-                            glyph_origin + baseline_offset,
-                            run.font_id,
-                            glyph.id,
-                            layout.font_size,
-                            color,
-                        )?;
+                        if glyph_batch_key != Some((run.font_id, color, synthesis)) {
+                            if let Some((font_id, color, synthesis)) = glyph_batch_key {
+                                cx.paint_glyph_run(
+                                    origin,
+                                    font_id,
+                                    layout.font_size,
+                                    synthesis,
+                                    color,
+                                    &glyph_batch,
+                                )?;
+                                glyph_batch.clear();
+                            }
+                            glyph_batch_key = Some((run.font_id, color, synthesis));
+                        }
+                        glyph_batch.push((glyph.id, glyph_origin + baseline_offset - origin));
                     }
                 }
             }
         }
 
+        if let Some((font_id, color, synthesis)) = glyph_batch_key {
+            cx.paint_glyph_run(
+                origin,
+                font_id,
+                layout.font_size,
+                synthesis,
+                color,
+                &glyph_batch,
+            )?;
+        }
+
         let mut last_line_end_x = origin.x + layout.width;
         if let Some(boundary) = wrap_boundaries.last() {
             let run = &layout.runs[boundary.run_ix];
             let glyph = &run.glyphs[boundary.glyph_ix];
-            last_line_end_x -= glyph.position.x;
+            last_line_end_x += wrap_indent - glyph.position.x;
         }
 
-        if let Some((background_origin, background_color)) = current_background.take() {
-            cx.paint_quad(fill(
-                Bounds {
-                    origin: background_origin,
-                    size: size(last_line_end_x - background_origin.x, line_height),
-                },
-                background_color,
-            ));
+        if let Some((background_origin, background_color, rounds_left)) = current_background.take()
+        {
+            cx.paint_quad(
+                fill(
+                    Bounds {
+                        origin: background_origin,
+                        size: size(last_line_end_x - background_origin.x, line_height),
+                    },
+                    background_color,
+                )
+                .corner_radii(background_corners(
+                    background_corner_radius,
+                    rounds_left,
+                    true,
+                )),
+            );
         }
 
         if let Some((underline_start, underline_style)) = current_underline.take() {
@@ -322,3 +845,263 @@ fn paint_line(
         Ok(())
     })
 }
+
+fn outline_line(
+    line: &LineView,
+    origin: Point<Pixels>,
+    line_height: Pixels,
+    text_system: &TextSystem,
+) -> Result<Vec<Path<Pixels>>> {
+    let LineView {
+        layout,
+        text,
+        wrap_boundaries,
+        wrap_indent,
+        ..
+    } = *line;
+    let padding_top = (line_height - layout.ascent - layout.descent) / 2.;
+    let baseline_offset = point(px(0.), padding_top + layout.ascent);
+    let mut wraps = wrap_boundaries.iter().peekable();
+    let mut glyph_origin = origin;
+    let mut prev_glyph_position = Point::default();
+    let mut prev_glyph_font_id = None;
+    let mut paths = Vec::new();
+    for (run_ix, run) in layout.runs.iter().enumerate() {
+        for (glyph_ix, glyph) in run.glyphs.iter().enumerate() {
+            glyph_origin.x += glyph.position.x - prev_glyph_position.x;
+
+            if wraps.peek() == Some(&&WrapBoundary { run_ix, glyph_ix }) {
+                wraps.next();
+                if let Some(font_id) =
+                    prev_glyph_font_id.filter(|_| text[..glyph.index].ends_with(SOFT_HYPHEN))
+                {
+                    if let Some(hyphen) = text_system
+                        .platform_text_system
+                        .glyph_for_char(font_id, '-')
+                    {
+                        paths.push(text_system.glyph_outline_at(
+                            font_id,
+                            hyphen,
+                            layout.font_size,
+                            glyph_origin + baseline_offset,
+                        )?);
+                    }
+                }
+                glyph_origin.x = origin.x + wrap_indent;
+                glyph_origin.y += line_height;
+            }
+            prev_glyph_position = glyph.position;
+            prev_glyph_font_id = Some(run.font_id);
+
+            // Whitespace has no outline, and soft hyphens are only drawn where the line wraps.
+            if text[glyph.index..].starts_with(|ch: char| ch.is_whitespace() || ch == SOFT_HYPHEN) {
+                continue;
+            }
+            paths.push(text_system.glyph_outline_at(
+                run.font_id,
+                glyph.id,
+                layout.font_size,
+                glyph_origin + baseline_offset,
+            )?);
+        }
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        self as gpui, canvas, font, red, Styled, TestAppContext, TestTextSystem, TextRun,
+        WindowTextSystem, WrapOptions,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_invisible_substitutes() {
+        let invisibles = InvisibleStyle {
+            trailing_color: Some(red()),
+            ..InvisibleStyle::new(black())
+        };
+
+        assert_eq!(invisibles.substitute(' ', false), Some(('·', black())));
+        assert_eq!(invisibles.substitute('\t', false), Some(('→', black())));
+        assert_eq!(invisibles.substitute(' ', true), Some(('·', red())));
+        assert_eq!(invisibles.substitute('a', false), None);
+        assert_eq!(
+            InvisibleStyle::new(black()).substitute('\t', true),
+            Some(('→', black()))
+        );
+    }
+
+    #[test]
+    fn test_rounded_corners() {
+        let radius = px(4.);
+        assert_eq!(selection_corners(radius, true, true), Corners::all(radius));
+        assert_eq!(
+            selection_corners(radius, true, false),
+            Corners {
+                top_left: radius,
+                top_right: radius,
+                bottom_right: px(0.),
+                bottom_left: px(0.),
+            }
+        );
+        assert_eq!(
+            selection_corners(radius, false, false),
+            Corners::all(px(0.))
+        );
+
+        assert_eq!(background_corners(radius, true, true), Corners::all(radius));
+        assert_eq!(
+            background_corners(radius, false, true),
+            Corners {
+                top_left: px(0.),
+                top_right: radius,
+                bottom_right: radius,
+                bottom_left: px(0.),
+            }
+        );
+    }
+
+    #[test]
+    fn test_word_boundaries() {
+        let text = "hello, wörld  foo_bar";
+
+        assert_eq!(word_range_at(text, 2), 0..5);
+        assert_eq!(word_range_at(text, 5), 5..6);
+        assert_eq!(word_range_at(text, 13), 13..15);
+        assert_eq!(word_range_at(text, text.len()), 15..22);
+        assert_eq!(word_range_at("", 0), 0..0);
+
+        assert_eq!(next_word_boundary(text, 0), 5);
+        assert_eq!(next_word_boundary(text, 5), 13);
+        assert_eq!(next_word_boundary(text, 13), 22);
+        assert_eq!(next_word_boundary(text, 22), 22);
+
+        assert_eq!(prev_word_boundary(text, 22), 15);
+        assert_eq!(prev_word_boundary(text, 15), 7);
+        assert_eq!(prev_word_boundary(text, 7), 0);
+        assert_eq!(prev_word_boundary(text, 0), 0);
+    }
+
+    #[test]
+    fn test_word_boundaries_between_ideographs() {
+        let text = "日本語";
+
+        assert_eq!(word_range_at(text, 3), 3..6);
+        assert_eq!(next_word_boundary(text, 0), 3);
+        assert_eq!(prev_word_boundary(text, 9), 6);
+    }
+
+    #[gpui::test]
+    fn test_marked_range_is_underlined(cx: &mut TestAppContext) {
+        cx.use_test_text_system();
+        let cx = cx.add_empty_window();
+        let underlines = Rc::new(RefCell::new(Vec::new()));
+
+        cx.draw(point(px(0.), px(0.)), size(px(200.), px(40.)), {
+            let underlines = underlines.clone();
+            move |_| {
+                canvas(
+                    |_, _| {},
+                    move |_, _, cx| {
+                        let run = TextRun {
+                            len: 11,
+                            font: font("Zed Test"),
+                            color: red(),
+                            background_color: None,
+                            underline: None,
+                            strikethrough: None,
+                        };
+                        let line = cx
+                            .text_system()
+                            .shape_line("hello world".into(), px(10.), &[run])
+                            .unwrap()
+                            .with_marked_range(6..11, UnderlineStyle::default());
+                        line.paint(point(px(0.), px(0.)), px(20.), cx).unwrap();
+                        let scene = &cx.window.next_frame.scene;
+                        underlines
+                            .borrow_mut()
+                            .extend(scene.underlines.iter().cloned());
+                    },
+                )
+                .size_full()
+            }
+        });
+
+        // The test font advances 5px per character at this size, and has an ascent of 8px, a
+        // descent of 2px and an underline thickness of 0.5px. The window's scale factor is 2.
+        let underlines = underlines.borrow();
+        assert_eq!(underlines.len(), 1);
+        let underline = &underlines[0];
+        let padding_top = px(5.);
+        let underline_y = padding_top + px(8.) + px(2.) * 0.618;
+        assert_eq!(
+            underline.bounds.origin,
+            point(px(30.), underline_y).scale(2.)
+        );
+        assert_eq!(underline.bounds.size.width, px(25.).scale(2.));
+        assert_eq!(underline.thickness, px(0.5).scale(2.));
+        assert_eq!(underline.color, red());
+    }
+
+    #[test]
+    fn test_bounds_for_range() {
+        let text_system =
+            WindowTextSystem::new(Arc::new(TextSystem::new(Arc::new(TestTextSystem))));
+        let run = |len| TextRun {
+            len,
+            font: font("Zed Test"),
+            color: black(),
+            background_color: None,
+            underline: None,
+            strikethrough: None,
+        };
+        let origin = point(px(10.), px(20.));
+        let line_height = px(18.);
+
+        let line = text_system
+            .shape_line("abcd".into(), px(16.), &[run(4)])
+            .unwrap();
+        let bounds = line.bounds_for_range(1..3, origin, line_height);
+        assert_eq!(
+            bounds.origin,
+            point(origin.x + line.x_for_index(1), origin.y)
+        );
+        assert_eq!(
+            bounds.size,
+            size(line.x_for_index(3) - line.x_for_index(1), line_height)
+        );
+        let caret = line.bounds_for_range(2..2, origin, line_height);
+        assert_eq!(caret.origin.x, origin.x + line.x_for_index(2));
+        assert_eq!(caret.size.width, px(0.));
+
+        // Only the part of the range on the first visual line is reported.
+        let wrapped = text_system
+            .shape_text(
+                "aaaa aaaa aaaa aaaa".into(),
+                px(16.),
+                &[run(19)],
+                WrapOptions {
+                    width: Some(px(100.)),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(wrapped.wrap_boundaries().len(), 1);
+        let spanning = wrapped.layout.bounds_for_range(3..14, line_height);
+        assert_eq!(spanning.len(), 2);
+        assert_eq!(
+            wrapped.first_bounds_for_range(3..14, origin, line_height),
+            Some(Bounds::new(origin + spanning[0].origin, spanning[0].size))
+        );
+        let caret = wrapped
+            .first_bounds_for_range(14..14, origin, line_height)
+            .unwrap();
+        assert_eq!(caret.origin.y, origin.y + line_height);
+        assert_eq!(caret.size.width, px(0.));
+    }
+}
@@ -1,12 +1,16 @@
-use crate::{point, px, FontId, GlyphId, Pixels, PlatformTextSystem, Point, Size};
+use crate::{
+    point, px, size, Bounds, FontId, GlyphId, Pixels, PlatformTextSystem, Point, Size, WrapMode,
+    SOFT_HYPHEN,
+};
 use collections::FxHashMap;
 use parking_lot::{Mutex, RwLock, RwLockUpgradableReadGuard};
 use smallvec::SmallVec;
 use std::{
     borrow::Borrow,
     hash::{Hash, Hasher},
+    iter, mem,
     ops::Range,
-    sync::Arc,
+    sync::{Arc, Weak},
 };
 
 /// A laid out and styled line of text
@@ -92,15 +96,51 @@ impl LineLayout {
     }
 
     /// The x position of the character at the given index
+    ///
+    /// Indices inside a cluster that spans several characters, such as a ligature, are spread
+    /// across the cluster's advance in proportion to their byte offset, so the caret can be
+    /// placed between the components of "=>" or "fi".
     pub fn x_for_index(&self, index: usize) -> Pixels {
-        for run in &self.runs {
-            for glyph in &run.glyphs {
-                if glyph.index >= index {
-                    return glyph.position.x;
+        match self.cluster_for_index(index) {
+            Some((indices, xs)) => {
+                let progress = (index - indices.start) as f32 / indices.len() as f32;
+                xs.start + (xs.end - xs.start) * progress.min(1.)
+            }
+            None => self
+                .runs
+                .iter()
+                .flat_map(|run| &run.glyphs)
+                .next()
+                .map_or(self.width, |glyph| glyph.position.x),
+        }
+    }
+
+    /// The caret positions at each character boundary of the glyph cluster containing the given
+    /// index, from the start of the cluster to its end. `text` must be the text of this line.
+    pub fn caret_positions_in_cluster(&self, text: &str, index: usize) -> SmallVec<[Pixels; 4]> {
+        let Some((indices, _)) = self.cluster_for_index(index) else {
+            return SmallVec::new();
+        };
+        (indices.start..=indices.end)
+            .filter(|ix| text.is_char_boundary(*ix))
+            .map(|ix| self.x_for_index(ix))
+            .collect()
+    }
+
+    /// The byte range and horizontal extent of the glyph cluster containing the given index.
+    fn cluster_for_index(&self, index: usize) -> Option<(Range<usize>, Range<Pixels>)> {
+        let mut cluster = None;
+        for glyph in self.runs.iter().flat_map(|run| &run.glyphs) {
+            match cluster {
+                Some((start, start_x)) if glyph.index > index => {
+                    return Some((start..glyph.index, start_x..glyph.position.x));
                 }
+                Some((start, _)) if glyph.index == start => {}
+                _ if glyph.index <= index => cluster = Some((glyph.index, glyph.position.x)),
+                _ => return None,
             }
         }
-        self.width
+        cluster.map(|(start, start_x)| (start..self.len, start_x..self.width))
     }
 
     /// The corresponding Font at the given index
@@ -115,10 +155,57 @@ impl LineLayout {
         None
     }
 
+    fn estimated_size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self
+                .runs
+                .iter()
+                .map(|run| {
+                    mem::size_of::<ShapedRun>()
+                        + if run.glyphs.spilled() {
+                            run.glyphs.capacity() * mem::size_of::<ShapedGlyph>()
+                        } else {
+                            0
+                        }
+                })
+                .sum::<usize>()
+    }
+
+    /// Gives soft hyphens no advance, as they are only displayed when a line is wrapped at
+    /// them. See [`crate::WrappedLine::paint`].
+    fn collapse_soft_hyphens(&mut self, text: &str) {
+        if !text.contains(SOFT_HYPHEN) {
+            return;
+        }
+
+        let mut shift = px(0.);
+        let mut soft_hyphen_x = None;
+        for run in &mut self.runs {
+            for glyph in &mut run.glyphs {
+                if let Some(soft_hyphen_x) = soft_hyphen_x.take() {
+                    shift += glyph.position.x - soft_hyphen_x;
+                }
+                if text[glyph.index..].starts_with(SOFT_HYPHEN) {
+                    soft_hyphen_x = Some(glyph.position.x);
+                }
+                glyph.position.x -= shift;
+            }
+        }
+        if let Some(soft_hyphen_x) = soft_hyphen_x {
+            shift += self.width - soft_hyphen_x;
+        }
+        self.width -= shift;
+    }
+
+    /// Finds where the line wraps. A line wrapped at a soft hyphen ends with a hyphen, whose
+    /// width in the font of the glyph before it is given by `hyphen_width`.
     fn compute_wrap_boundaries(
         &self,
         text: &str,
         wrap_width: Pixels,
+        wrap_indent: Pixels,
+        wrap_mode: WrapMode,
+        hyphen_width: impl Fn(FontId) -> Pixels,
     ) -> SmallVec<[WrapBoundary; 1]> {
         let mut boundaries = SmallVec::new();
 
@@ -130,7 +217,9 @@ impl LineLayout {
             glyph_ix: 0,
         };
         let mut last_boundary_x = px(0.);
-        let mut prev_ch = '\0';
+        let mut break_opportunities = unicode_linebreak::linebreaks(text)
+            .map(|(ix, _)| ix)
+            .peekable();
         let mut glyphs = self
             .runs
             .iter()
@@ -140,30 +229,58 @@ impl LineLayout {
                     let character = text[glyph.index..].chars().next().unwrap();
                     (
                         WrapBoundary { run_ix, glyph_ix },
+                        glyph.index,
                         character,
                         glyph.position.x,
+                        run.font_id,
                     )
                 })
             })
             .peekable();
 
-        while let Some((boundary, ch, x)) = glyphs.next() {
+        let mut prev_font_id = None;
+        while let Some((boundary, ix, ch, x, font_id)) = glyphs.next() {
             if ch == '\n' {
                 continue;
             }
 
-            if prev_ch == ' ' && ch != ' ' && first_non_whitespace_ix.is_some() {
-                last_candidate_ix = Some(boundary);
-                last_candidate_x = x;
+            let line_wrap_width = if boundaries.is_empty() {
+                wrap_width
+            } else {
+                wrap_width - wrap_indent
+            };
+            while break_opportunities
+                .next_if(|&break_ix| break_ix < ix)
+                .is_some()
+            {}
+            let is_break_opportunity = break_opportunities.next_if_eq(&ix).is_some();
+            if is_break_opportunity
+                && first_non_whitespace_ix.is_some()
+                && wrap_mode != WrapMode::Anywhere
+            {
+                // Breaking after a soft hyphen shows a hyphen, which has to fit on the line too.
+                let hyphen_fits = prev_font_id
+                    .filter(|_| text[..ix].ends_with(SOFT_HYPHEN))
+                    .map_or(true, |font_id| {
+                        x + hyphen_width(font_id) - last_boundary_x <= line_wrap_width
+                    });
+                if hyphen_fits {
+                    last_candidate_ix = Some(boundary);
+                    last_candidate_x = x;
+                }
             }
+            prev_font_id = Some(font_id);
 
             if ch != ' ' && first_non_whitespace_ix.is_none() {
                 first_non_whitespace_ix = Some(boundary);
             }
 
-            let next_x = glyphs.peek().map_or(self.width, |(_, _, x)| *x);
+            let next_x = glyphs.peek().map_or(self.width, |(_, _, _, x, _)| *x);
             let width = next_x - last_boundary_x;
-            if width > wrap_width && boundary > last_boundary {
+            if width > line_wrap_width
+                && boundary > last_boundary
+                && (last_candidate_ix.is_some() || wrap_mode != WrapMode::WordBoundary)
+            {
                 if let Some(last_candidate_ix) = last_candidate_ix.take() {
                     last_boundary = last_candidate_ix;
                     last_boundary_x = last_candidate_x;
@@ -174,7 +291,6 @@ impl LineLayout {
 
                 boundaries.push(last_boundary);
             }
-            prev_ch = ch;
         }
 
         boundaries
@@ -192,6 +308,9 @@ pub struct WrappedLineLayout {
 
     /// The width of the line, if it was wrapped
     pub wrap_width: Option<Pixels>,
+
+    /// The indent of the second and later visual lines.
+    pub wrap_indent: Pixels,
 }
 
 /// A boundary at which a line was wrapped
@@ -203,6 +322,15 @@ pub struct WrapBoundary {
     pub glyph_ix: usize,
 }
 
+/// Why a visual line of wrapped text ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BreakReason {
+    /// The line ended at a newline, or at the end of the text.
+    HardBreak,
+    /// The line was wrapped to fit the wrap width.
+    SoftWrap,
+}
+
 impl WrappedLineLayout {
     /// The length of the underlying text, in utf8 bytes.
     #[allow(clippy::len_without_is_empty)]
@@ -236,11 +364,25 @@ impl WrappedLineLayout {
         self.unwrapped_layout.descent
     }
 
+    /// The indent of the second and later visual lines, as passed when wrapping the line.
+    pub fn wrap_indent(&self) -> Pixels {
+        self.wrap_indent
+    }
+
     /// The wrap boundaries in this layout
     pub fn wrap_boundaries(&self) -> &[WrapBoundary] {
         &self.wrap_boundaries
     }
 
+    /// Why each visual line in this layout ended, from first to last. Every visual line but
+    /// the last ends in a soft wrap.
+    pub fn break_reasons(&self) -> impl Iterator<Item = BreakReason> + '_ {
+        self.wrap_boundaries
+            .iter()
+            .map(|_| BreakReason::SoftWrap)
+            .chain(iter::once(BreakReason::HardBreak))
+    }
+
     /// The font size of this layout
     pub fn font_size(&self) -> Pixels {
         self.unwrapped_layout.font_size
@@ -252,12 +394,21 @@ impl WrappedLineLayout {
     }
 
     /// The index corresponding to a given position in this layout for the given line height.
+    ///
+    /// A position exactly at the bottom edge of the layout is treated as being on the last
+    /// visual line; positions strictly below it return `Err` with the length of the line.
     pub fn index_for_position(
         &self,
-        mut position: Point<Pixels>,
+        position: Point<Pixels>,
         line_height: Pixels,
     ) -> Result<usize, usize> {
-        let wrapped_line_ix = (position.y / line_height) as usize;
+        if position.y > self.size(line_height).height {
+            return Err(self.len());
+        }
+
+        // Clamp so that `position.y == height` maps onto the last line rather than
+        // one past it.
+        let wrapped_line_ix = ((position.y / line_height) as usize).min(self.wrap_boundaries.len());
 
         let wrapped_line_start_index;
         let wrapped_line_start_x;
@@ -289,6 +440,9 @@ impl WrappedLineLayout {
         };
 
         let mut position_in_unwrapped_line = position;
+        if wrapped_line_ix > 0 {
+            position_in_unwrapped_line.x -= self.wrap_indent;
+        }
         position_in_unwrapped_line.x += wrapped_line_start_x;
         if position_in_unwrapped_line.x < wrapped_line_start_x {
             Err(wrapped_line_start_index)
@@ -324,27 +478,232 @@ impl WrappedLineLayout {
                 continue;
             } else {
                 let line_start_x = self.unwrapped_layout.x_for_index(line_start_ix);
-                let x = self.unwrapped_layout.x_for_index(index) - line_start_x;
+                let mut x = self.unwrapped_layout.x_for_index(index) - line_start_x;
+                if ix > 0 {
+                    x += self.wrap_indent;
+                }
                 return Some(point(x, line_y));
             }
         }
 
         None
     }
+
+    /// Wrapped layouts share their unwrapped layout with the line cache, so only the
+    /// wrapping itself is counted here.
+    fn estimated_size(&self) -> usize {
+        mem::size_of::<Self>()
+            + if self.wrap_boundaries.spilled() {
+                self.wrap_boundaries.capacity() * mem::size_of::<WrapBoundary>()
+            } else {
+                0
+            }
+    }
+
+    /// Returns the bounds covered by the given byte range, relative to the origin of this
+    /// layout. The range is split into one rectangle per visual line that it intersects.
+    pub fn bounds_for_range(
+        &self,
+        range: Range<usize>,
+        line_height: Pixels,
+    ) -> SmallVec<[Bounds<Pixels>; 1]> {
+        let mut bounds = SmallVec::new();
+        for (ix, (line_range, line_x)) in self.visual_lines().enumerate() {
+            let start = range.start.max(line_range.start);
+            let end = range.end.min(line_range.end);
+            if start >= end {
+                continue;
+            }
+
+            let indent = if ix > 0 { self.wrap_indent } else { px(0.) };
+            let start_x = self.unwrapped_layout.x_for_index(start) - line_x.start + indent;
+            let end_x = self.unwrapped_layout.x_for_index(end) - line_x.start + indent;
+            bounds.push(Bounds::new(
+                point(start_x, ix as f32 * line_height),
+                size(end_x - start_x, line_height),
+            ));
+        }
+        bounds
+    }
+
+    /// The byte range and horizontal extent, in unwrapped coordinates, of each visual line.
+    fn visual_lines(&self) -> impl Iterator<Item = (Range<usize>, Range<Pixels>)> + '_ {
+        let boundaries = self.wrap_boundaries.iter().map(|boundary| {
+            let glyph = &self.unwrapped_layout.runs[boundary.run_ix].glyphs[boundary.glyph_ix];
+            (glyph.index, glyph.position.x)
+        });
+        let starts = iter::once((0, Pixels::ZERO)).chain(boundaries.clone());
+        let ends = boundaries.chain(iter::once((self.len(), self.unwrapped_layout.width)));
+        starts
+            .zip(ends)
+            .map(|((start, start_x), (end, end_x))| (start..end, start_x..end_x))
+    }
 }
 
 pub(crate) struct LineLayoutCache {
     previous_frame: Mutex<FrameCache>,
     current_frame: RwLock<FrameCache>,
+    counters: Mutex<CacheCounters>,
+    budget: Arc<RwLock<LineLayoutCacheBudget>>,
+    shared_lines: Arc<SharedLineLayouts>,
     platform_text_system: Arc<dyn PlatformTextSystem>,
 }
 
+/// Counts of how layout requests were served by the line layout cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineLayoutCacheCounters {
+    /// Requests served by a layout already used during the same frame.
+    pub hits: usize,
+    /// Requests served by carrying over a layout used in the previous frame.
+    pub reused_from_previous_frame: usize,
+    /// Requests served by a layout that another window had already computed.
+    pub shared_hits: usize,
+    /// Requests that required the text to be laid out.
+    pub misses: usize,
+}
+
+/// A snapshot of the state of a window's line layout cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineLayoutCacheStats {
+    /// The counters for the frame currently being drawn.
+    pub current_frame: LineLayoutCacheCounters,
+    /// The counters for the last completed frame.
+    pub previous_frame: LineLayoutCacheCounters,
+    /// The counters accumulated over the lifetime of the cache.
+    pub total: LineLayoutCacheCounters,
+    /// The number of layouts retained by the cache, across both frames.
+    pub entries: usize,
+    /// A rough estimate of the heap and inline memory used by the retained layouts, in bytes.
+    pub estimated_bytes: usize,
+}
+
+/// Limits on the number of layouts a window's line layout cache retains.
+///
+/// Layouts used during the current frame are always retained. When a limit is exceeded,
+/// layouts carried over from the previous frame are evicted in the order they were used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineLayoutCacheBudget {
+    /// The maximum number of layouts to retain, across both frames.
+    pub max_entries: Option<usize>,
+    /// The maximum estimated size of the retained layouts, in bytes.
+    pub max_bytes: Option<usize>,
+}
+
+impl LineLayoutCacheBudget {
+    fn is_exceeded(&self, entries: usize, bytes: usize) -> bool {
+        self.max_entries.map_or(false, |max| entries > max)
+            || self.max_bytes.map_or(false, |max| bytes > max)
+    }
+}
+
+#[derive(Default)]
+struct CacheCounters {
+    current_frame: LineLayoutCacheCounters,
+    previous_frame: LineLayoutCacheCounters,
+    total: LineLayoutCacheCounters,
+}
+
 #[derive(Default)]
 struct FrameCache {
     lines: FxHashMap<Arc<CacheKey>, Arc<LineLayout>>,
     wrapped_lines: FxHashMap<Arc<CacheKey>, Arc<WrappedLineLayout>>,
     used_lines: Vec<Arc<CacheKey>>,
     used_wrapped_lines: Vec<Arc<CacheKey>>,
+    estimated_bytes: usize,
+}
+
+impl FrameCache {
+    fn len(&self) -> usize {
+        self.lines.len() + self.wrapped_lines.len()
+    }
+
+    fn insert_line(&mut self, key: Arc<CacheKey>, layout: Arc<LineLayout>) {
+        self.estimated_bytes += key.estimated_size() + layout.estimated_size();
+        if let Some(previous) = self.lines.insert(key.clone(), layout) {
+            self.estimated_bytes -= key.estimated_size() + previous.estimated_size();
+        }
+    }
+
+    fn remove_line(&mut self, key: &dyn AsCacheKeyRef) -> Option<(Arc<CacheKey>, Arc<LineLayout>)> {
+        let (key, layout) = self.lines.remove_entry(key)?;
+        self.estimated_bytes -= key.estimated_size() + layout.estimated_size();
+        Some((key, layout))
+    }
+
+    fn insert_wrapped_line(&mut self, key: Arc<CacheKey>, layout: Arc<WrappedLineLayout>) {
+        self.estimated_bytes += key.estimated_size() + layout.estimated_size();
+        if let Some(previous) = self.wrapped_lines.insert(key.clone(), layout) {
+            self.estimated_bytes -= key.estimated_size() + previous.estimated_size();
+        }
+    }
+
+    fn remove_wrapped_line(
+        &mut self,
+        key: &dyn AsCacheKeyRef,
+    ) -> Option<(Arc<CacheKey>, Arc<WrappedLineLayout>)> {
+        let (key, layout) = self.wrapped_lines.remove_entry(key)?;
+        self.estimated_bytes -= key.estimated_size() + layout.estimated_size();
+        Some((key, layout))
+    }
+
+    fn clear(&mut self) {
+        self.lines.clear();
+        self.wrapped_lines.clear();
+        self.used_lines.clear();
+        self.used_wrapped_lines.clear();
+        self.estimated_bytes = 0;
+    }
+
+    /// Evicts layouts in the order they were used while `is_over_budget` returns true.
+    /// The usage order itself is preserved, so that ranges of it can still be reused.
+    fn evict(&mut self, is_over_budget: impl Fn(&FrameCache) -> bool) {
+        // Wrapped layouts hold on to their unwrapped layout, so evict them first.
+        let mut ix = 0;
+        while ix < self.used_wrapped_lines.len() && is_over_budget(self) {
+            let key = self.used_wrapped_lines[ix].clone();
+            self.remove_wrapped_line(key.as_ref());
+            ix += 1;
+        }
+
+        let mut ix = 0;
+        while ix < self.used_lines.len() && is_over_budget(self) {
+            let key = self.used_lines[ix].clone();
+            self.remove_line(key.as_ref());
+            ix += 1;
+        }
+    }
+}
+
+/// Unwrapped line layouts shared between the caches of every window using the same text system.
+///
+/// Entries don't keep layouts alive: a layout is only retained while at least one window's
+/// cache is still using it, so the per-window caches remain in charge of lifetimes.
+#[derive(Default)]
+pub(crate) struct SharedLineLayouts(Mutex<SharedLines>);
+
+#[derive(Default)]
+struct SharedLines {
+    lines: FxHashMap<Arc<CacheKey>, Weak<LineLayout>>,
+    prune_threshold: usize,
+}
+
+impl SharedLineLayouts {
+    const MIN_PRUNE_THRESHOLD: usize = 256;
+
+    fn get(&self, key: &dyn AsCacheKeyRef) -> Option<Arc<LineLayout>> {
+        self.0.lock().lines.get(key)?.upgrade()
+    }
+
+    fn insert(&self, key: Arc<CacheKey>, layout: &Arc<LineLayout>) {
+        let mut shared = self.0.lock();
+        shared.lines.insert(key, Arc::downgrade(layout));
+
+        // Drop entries that are no longer used by any window, amortized over insertions.
+        if shared.lines.len() > shared.prune_threshold {
+            shared.lines.retain(|_, layout| layout.strong_count() > 0);
+            shared.prune_threshold = (shared.lines.len() * 2).max(Self::MIN_PRUNE_THRESHOLD);
+        }
+    }
 }
 
 #[derive(Clone, Default)]
@@ -354,14 +713,61 @@ pub(crate) struct LineLayoutIndex {
 }
 
 impl LineLayoutCache {
-    pub fn new(platform_text_system: Arc<dyn PlatformTextSystem>) -> Self {
+    pub fn new(
+        platform_text_system: Arc<dyn PlatformTextSystem>,
+        budget: Arc<RwLock<LineLayoutCacheBudget>>,
+        shared_lines: Arc<SharedLineLayouts>,
+    ) -> Self {
         Self {
             previous_frame: Mutex::default(),
             current_frame: RwLock::default(),
+            counters: Mutex::default(),
+            budget,
+            shared_lines,
             platform_text_system,
         }
     }
 
+    pub fn stats(&self) -> LineLayoutCacheStats {
+        let counters = self.counters.lock();
+        let mut stats = LineLayoutCacheStats {
+            current_frame: counters.current_frame,
+            previous_frame: counters.previous_frame,
+            total: counters.total,
+            ..Default::default()
+        };
+        drop(counters);
+
+        let current_frame = self.current_frame.read();
+        let previous_frame = self.previous_frame.lock();
+        for frame in [&*previous_frame, &*current_frame] {
+            stats.entries += frame.len();
+            stats.estimated_bytes += frame.estimated_bytes;
+        }
+        stats
+    }
+
+    fn record(&self, update: impl Fn(&mut LineLayoutCacheCounters)) {
+        let mut counters = self.counters.lock();
+        update(&mut counters.current_frame);
+        update(&mut counters.total);
+    }
+
+    /// Evicts layouts carried over from the previous frame until the cache fits its budget.
+    fn enforce_budget(&self, current_frame: &FrameCache) {
+        let budget = *self.budget.read();
+        if budget == LineLayoutCacheBudget::default() {
+            return;
+        }
+
+        self.previous_frame.lock().evict(|previous_frame| {
+            budget.is_exceeded(
+                previous_frame.len() + current_frame.len(),
+                previous_frame.estimated_bytes + current_frame.estimated_bytes,
+            )
+        });
+    }
+
     pub fn layout_index(&self) -> LineLayoutIndex {
         let frame = self.current_frame.read();
         LineLayoutIndex {
@@ -374,20 +780,20 @@ impl LineLayoutCache {
         let mut previous_frame = &mut *self.previous_frame.lock();
         let mut current_frame = &mut *self.current_frame.write();
 
-        for key in &previous_frame.used_lines[range.start.lines_index..range.end.lines_index] {
-            if let Some((key, line)) = previous_frame.lines.remove_entry(key) {
-                current_frame.lines.insert(key, line);
+        for ix in range.start.lines_index..range.end.lines_index {
+            let key = previous_frame.used_lines[ix].clone();
+            if let Some((key, line)) = previous_frame.remove_line(key.as_ref()) {
+                current_frame.insert_line(key, line);
             }
-            current_frame.used_lines.push(key.clone());
+            current_frame.used_lines.push(key);
         }
 
-        for key in &previous_frame.used_wrapped_lines
-            [range.start.wrapped_lines_index..range.end.wrapped_lines_index]
-        {
-            if let Some((key, line)) = previous_frame.wrapped_lines.remove_entry(key) {
-                current_frame.wrapped_lines.insert(key, line);
+        for ix in range.start.wrapped_lines_index..range.end.wrapped_lines_index {
+            let key = previous_frame.used_wrapped_lines[ix].clone();
+            if let Some((key, line)) = previous_frame.remove_wrapped_line(key.as_ref()) {
+                current_frame.insert_wrapped_line(key, line);
             }
-            current_frame.used_wrapped_lines.push(key.clone());
+            current_frame.used_wrapped_lines.push(key);
         }
     }
 
@@ -403,10 +809,10 @@ impl LineLayoutCache {
         let mut prev_frame = self.previous_frame.lock();
         let mut curr_frame = self.current_frame.write();
         std::mem::swap(&mut *prev_frame, &mut *curr_frame);
-        curr_frame.lines.clear();
-        curr_frame.wrapped_lines.clear();
-        curr_frame.used_lines.clear();
-        curr_frame.used_wrapped_lines.clear();
+        curr_frame.clear();
+
+        let mut counters = self.counters.lock();
+        counters.previous_frame = mem::take(&mut counters.current_frame);
     }
 
     pub fn layout_wrapped_line(
@@ -415,33 +821,45 @@ impl LineLayoutCache {
         font_size: Pixels,
         runs: &[FontRun],
         wrap_width: Option<Pixels>,
+        wrap_indent: Option<Pixels>,
+        wrap_mode: WrapMode,
     ) -> Arc<WrappedLineLayout> {
         let key = &CacheKeyRef {
             text,
             font_size,
             runs,
             wrap_width,
+            wrap_indent,
+            wrap_mode,
         } as &dyn AsCacheKeyRef;
 
         let current_frame = self.current_frame.upgradable_read();
         if let Some(layout) = current_frame.wrapped_lines.get(key) {
+            self.record(|counters| counters.hits += 1);
             return layout.clone();
         }
 
-        let previous_frame_entry = self.previous_frame.lock().wrapped_lines.remove_entry(key);
+        let previous_frame_entry = self.previous_frame.lock().remove_wrapped_line(key);
         if let Some((key, layout)) = previous_frame_entry {
+            self.record(|counters| counters.reused_from_previous_frame += 1);
             let mut current_frame = RwLockUpgradableReadGuard::upgrade(current_frame);
-            current_frame
-                .wrapped_lines
-                .insert(key.clone(), layout.clone());
+            current_frame.insert_wrapped_line(key.clone(), layout.clone());
             current_frame.used_wrapped_lines.push(key);
             layout
         } else {
             drop(current_frame);
+            self.record(|counters| counters.misses += 1);
 
             let unwrapped_layout = self.layout_line(text, font_size, runs);
+            let indent = wrap_indent.unwrap_or_default();
             let wrap_boundaries = if let Some(wrap_width) = wrap_width {
-                unwrapped_layout.compute_wrap_boundaries(text.as_ref(), wrap_width)
+                unwrapped_layout.compute_wrap_boundaries(
+                    text.as_ref(),
+                    wrap_width,
+                    indent,
+                    wrap_mode,
+                    |font_id| self.hyphen_width(font_id, font_size),
+                )
             } else {
                 SmallVec::new()
             };
@@ -449,52 +867,100 @@ impl LineLayoutCache {
                 unwrapped_layout,
                 wrap_boundaries,
                 wrap_width,
+                wrap_indent: indent,
             });
             let key = Arc::new(CacheKey {
                 text: text.into(),
                 font_size,
                 runs: SmallVec::from(runs),
                 wrap_width,
+                wrap_indent,
+                wrap_mode,
             });
 
             let mut current_frame = self.current_frame.write();
-            current_frame
-                .wrapped_lines
-                .insert(key.clone(), layout.clone());
+            current_frame.insert_wrapped_line(key.clone(), layout.clone());
             current_frame.used_wrapped_lines.push(key);
+            self.enforce_budget(&current_frame);
 
             layout
         }
     }
 
+    /// The advance of a hyphen in the given font, which is shown when a line wraps at a soft
+    /// hyphen.
+    fn hyphen_width(&self, font_id: FontId, font_size: Pixels) -> Pixels {
+        let Some(glyph_id) = self.platform_text_system.glyph_for_char(font_id, '-') else {
+            return px(0.);
+        };
+        let units_per_em = self.platform_text_system.font_metrics(font_id).units_per_em;
+        self.platform_text_system
+            .advance(font_id, glyph_id)
+            .map_or(px(0.), |advance| {
+                px(advance.width / units_per_em as f32 * font_size.0)
+            })
+    }
+
     pub fn layout_line(&self, text: &str, font_size: Pixels, runs: &[FontRun]) -> Arc<LineLayout> {
         let key = &CacheKeyRef {
             text,
             font_size,
             runs,
             wrap_width: None,
+            wrap_indent: None,
+            wrap_mode: WrapMode::default(),
         } as &dyn AsCacheKeyRef;
 
         let current_frame = self.current_frame.upgradable_read();
         if let Some(layout) = current_frame.lines.get(key) {
+            self.record(|counters| counters.hits += 1);
             return layout.clone();
         }
 
-        let mut current_frame = RwLockUpgradableReadGuard::upgrade(current_frame);
-        if let Some((key, layout)) = self.previous_frame.lock().lines.remove_entry(key) {
-            current_frame.lines.insert(key.clone(), layout.clone());
+        let previous_frame_entry = self.previous_frame.lock().remove_line(key);
+        if let Some((key, layout)) = previous_frame_entry {
+            self.record(|counters| counters.reused_from_previous_frame += 1);
+            let mut current_frame = RwLockUpgradableReadGuard::upgrade(current_frame);
+            current_frame.insert_line(key.clone(), layout.clone());
             current_frame.used_lines.push(key);
             layout
         } else {
-            let layout = Arc::new(self.platform_text_system.layout_line(text, font_size, runs));
+            // Don't hold the cache lock while shaping, so that other threads laying out text
+            // for this window aren't blocked behind the platform text system.
+            drop(current_frame);
+
             let key = Arc::new(CacheKey {
                 text: text.into(),
                 font_size,
                 runs: SmallVec::from(runs),
                 wrap_width: None,
+                wrap_indent: None,
+                wrap_mode: WrapMode::default(),
+            });
+            let shared_layout = self.shared_lines.get(key.as_ref());
+            let is_shared = shared_layout.is_some();
+            let layout = shared_layout.unwrap_or_else(|| {
+                let mut layout = self.platform_text_system.layout_line(text, font_size, runs);
+                layout.collapse_soft_hyphens(text);
+                Arc::new(layout)
             });
-            current_frame.lines.insert(key.clone(), layout.clone());
+
+            let mut current_frame = self.current_frame.write();
+            if let Some(existing) = current_frame.lines.get(&key) {
+                // Another thread laid out the same line while the lock was released. Use its
+                // layout, so that the one shared with other windows stays alive.
+                self.record(|counters| counters.hits += 1);
+                return existing.clone();
+            }
+            if is_shared {
+                self.record(|counters| counters.shared_hits += 1);
+            } else {
+                self.record(|counters| counters.misses += 1);
+                self.shared_lines.insert(key.clone(), &layout);
+            }
+            current_frame.insert_line(key.clone(), layout.clone());
             current_frame.used_lines.push(key);
+            self.enforce_budget(&current_frame);
             layout
         }
     }
@@ -517,6 +983,8 @@ struct CacheKey {
     font_size: Pixels,
     runs: SmallVec<[FontRun; 1]>,
     wrap_width: Option<Pixels>,
+    wrap_indent: Option<Pixels>,
+    wrap_mode: WrapMode,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
@@ -525,6 +993,8 @@ struct CacheKeyRef<'a> {
     font_size: Pixels,
     runs: &'a [FontRun],
     wrap_width: Option<Pixels>,
+    wrap_indent: Option<Pixels>,
+    wrap_mode: WrapMode,
 }
 
 impl<'a> PartialEq for (dyn AsCacheKeyRef + 'a) {
@@ -541,6 +1011,18 @@ impl<'a> Hash for (dyn AsCacheKeyRef + 'a) {
     }
 }
 
+impl CacheKey {
+    fn estimated_size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.text.capacity()
+            + if self.runs.spilled() {
+                self.runs.capacity() * mem::size_of::<FontRun>()
+            } else {
+                0
+            }
+    }
+}
+
 impl AsCacheKeyRef for CacheKey {
     fn as_cache_key_ref(&self) -> CacheKeyRef {
         CacheKeyRef {
@@ -548,6 +1030,8 @@ impl AsCacheKeyRef for CacheKey {
             font_size: self.font_size,
             runs: self.runs.as_slice(),
             wrap_width: self.wrap_width,
+            wrap_indent: self.wrap_indent,
+            wrap_mode: self.wrap_mode,
         }
     }
 }
@@ -575,3 +1059,615 @@ impl<'a> AsCacheKeyRef for CacheKeyRef<'a> {
         *self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DevicePixels, Font, FontMetrics, FontStyle, FontWeight, OutlineSegment, RenderGlyphParams,
+        TestTextSystem,
+    };
+    use anyhow::Result;
+    use parking_lot::Condvar;
+    use std::{
+        borrow::Cow,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    fn glyph(index: usize, x: f32) -> ShapedGlyph {
+        ShapedGlyph {
+            id: GlyphId(index as u32),
+            position: point(px(x), px(0.)),
+            index,
+            is_emoji: false,
+        }
+    }
+
+    /// "abcdef" with 10px wide glyphs, wrapped after "abc".
+    fn wrapped_layout() -> WrappedLineLayout {
+        WrappedLineLayout {
+            unwrapped_layout: Arc::new(LineLayout {
+                font_size: px(16.),
+                width: px(60.),
+                ascent: px(12.),
+                descent: px(4.),
+                runs: vec![ShapedRun {
+                    font_id: FontId(0),
+                    glyphs: (0..6).map(|ix| glyph(ix, ix as f32 * 10.)).collect(),
+                }],
+                len: 6,
+            }),
+            wrap_boundaries: SmallVec::from_slice(&[WrapBoundary {
+                run_ix: 0,
+                glyph_ix: 3,
+            }]),
+            wrap_width: Some(px(30.)),
+            wrap_indent: px(0.),
+        }
+    }
+
+    #[test]
+    fn test_index_for_position_at_layout_bottom() {
+        let layout = wrapped_layout();
+        let line_height = px(20.);
+        assert_eq!(layout.size(line_height).height, px(40.));
+
+        assert_eq!(
+            layout.index_for_position(point(px(5.), px(40.)), line_height),
+            Ok(3)
+        );
+        assert_eq!(
+            layout.index_for_position(point(px(15.), px(39.99)), line_height),
+            Ok(4)
+        );
+        assert_eq!(
+            layout.index_for_position(point(px(50.), px(40.)), line_height),
+            Err(6)
+        );
+        assert_eq!(
+            layout.index_for_position(point(px(5.), px(40.01)), line_height),
+            Err(6)
+        );
+    }
+
+    #[test]
+    fn test_index_for_position_on_first_line() {
+        let layout = wrapped_layout();
+        let line_height = px(20.);
+
+        assert_eq!(
+            layout.index_for_position(point(px(15.), px(0.)), line_height),
+            Ok(1)
+        );
+        assert_eq!(
+            layout.index_for_position(point(px(35.), px(19.99)), line_height),
+            Err(3)
+        );
+    }
+
+    #[test]
+    fn test_break_reasons() {
+        let layout = wrapped_layout();
+        assert_eq!(
+            layout.break_reasons().collect::<Vec<_>>(),
+            &[BreakReason::SoftWrap, BreakReason::HardBreak]
+        );
+
+        let unwrapped = WrappedLineLayout {
+            wrap_boundaries: SmallVec::new(),
+            ..wrapped_layout()
+        };
+        assert_eq!(
+            unwrapped.break_reasons().collect::<Vec<_>>(),
+            &[BreakReason::HardBreak]
+        );
+    }
+
+    #[test]
+    fn test_bounds_for_range() {
+        let layout = wrapped_layout();
+        let line_height = px(20.);
+
+        assert_eq!(
+            layout.bounds_for_range(1..5, line_height).as_slice(),
+            &[
+                Bounds::new(point(px(10.), px(0.)), size(px(20.), px(20.))),
+                Bounds::new(point(px(0.), px(20.)), size(px(20.), px(20.))),
+            ]
+        );
+        assert_eq!(
+            layout.bounds_for_range(4..6, line_height).as_slice(),
+            &[Bounds::new(point(px(10.), px(20.)), size(px(20.), px(20.)))]
+        );
+        assert!(layout.bounds_for_range(2..2, line_height).is_empty());
+    }
+
+    #[test]
+    fn test_x_for_index_inside_ligature() {
+        // "a=>b", where "=>" is a single 20px wide ligature glyph.
+        let layout = LineLayout {
+            font_size: px(16.),
+            width: px(40.),
+            ascent: px(12.),
+            descent: px(4.),
+            runs: vec![ShapedRun {
+                font_id: FontId(0),
+                glyphs: SmallVec::from_vec(vec![glyph(0, 0.), glyph(1, 10.), glyph(3, 30.)]),
+            }],
+            len: 4,
+        };
+
+        assert_eq!(layout.x_for_index(1), px(10.));
+        assert_eq!(layout.x_for_index(2), px(20.));
+        assert_eq!(layout.x_for_index(3), px(30.));
+        assert_eq!(layout.x_for_index(4), px(40.));
+        assert_eq!(
+            layout.caret_positions_in_cluster("a=>b", 2).as_slice(),
+            &[px(10.), px(20.), px(30.)]
+        );
+        assert_eq!(
+            layout.caret_positions_in_cluster("a=>b", 0).as_slice(),
+            &[px(0.), px(10.)]
+        );
+    }
+
+    /// Lays out `text` in the test font at 16px.
+    fn layout_line(cache: &LineLayoutCache, text: &str) -> Arc<LineLayout> {
+        let runs = [FontRun {
+            len: text.len(),
+            font_id: FontId(0),
+        }];
+        cache.layout_line(text, px(16.), &runs)
+    }
+
+    fn test_cache(budget: LineLayoutCacheBudget) -> LineLayoutCache {
+        LineLayoutCache::new(
+            Arc::new(TestTextSystem),
+            Arc::new(RwLock::new(budget)),
+            Arc::default(),
+        )
+    }
+
+    #[test]
+    fn test_wrap_indent() {
+        let layout = WrappedLineLayout {
+            wrap_indent: px(5.),
+            ..wrapped_layout()
+        };
+        let line_height = px(20.);
+
+        assert_eq!(
+            layout.position_for_index(1, line_height),
+            Some(point(px(10.), px(0.)))
+        );
+        assert_eq!(
+            layout.position_for_index(4, line_height),
+            Some(point(px(15.), px(20.)))
+        );
+        assert_eq!(
+            layout.index_for_position(point(px(2.), px(25.)), line_height),
+            Err(3)
+        );
+        assert_eq!(
+            layout.index_for_position(point(px(16.), px(25.)), line_height),
+            Ok(4)
+        );
+        assert_eq!(
+            layout.bounds_for_range(4..6, line_height).as_slice(),
+            &[Bounds::new(point(px(15.), px(20.)), size(px(20.), px(20.)))]
+        );
+    }
+
+    #[test]
+    fn test_collapse_soft_hyphens() {
+        // "ab\u{ad}cd", where every glyph is 10px wide.
+        let text = "ab\u{ad}cd";
+        let mut layout = LineLayout {
+            font_size: px(16.),
+            width: px(50.),
+            ascent: px(12.),
+            descent: px(4.),
+            runs: vec![ShapedRun {
+                font_id: FontId(0),
+                glyphs: SmallVec::from_vec(vec![
+                    glyph(0, 0.),
+                    glyph(1, 10.),
+                    glyph(2, 20.),
+                    glyph(4, 30.),
+                    glyph(5, 40.),
+                ]),
+            }],
+            len: text.len(),
+        };
+        layout.collapse_soft_hyphens(text);
+
+        let xs = layout.runs[0]
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.position.x)
+            .collect::<Vec<_>>();
+        assert_eq!(xs, &[px(0.), px(10.), px(20.), px(20.), px(30.)]);
+        assert_eq!(layout.width, px(40.));
+    }
+
+    #[test]
+    fn test_wrap_at_soft_hyphen_reserves_hyphen_width() {
+        // "ab cd\u{ad}ef", where every glyph but the soft hyphen is 10px wide.
+        let text = "ab cd\u{ad}ef";
+        let mut layout = LineLayout {
+            font_size: px(16.),
+            width: px(80.),
+            ascent: px(12.),
+            descent: px(4.),
+            runs: vec![ShapedRun {
+                font_id: FontId(0),
+                glyphs: SmallVec::from_vec(vec![
+                    glyph(0, 0.),
+                    glyph(1, 10.),
+                    glyph(2, 20.),
+                    glyph(3, 30.),
+                    glyph(4, 40.),
+                    glyph(5, 50.),
+                    glyph(7, 60.),
+                    glyph(8, 70.),
+                ]),
+            }],
+            len: text.len(),
+        };
+        layout.collapse_soft_hyphens(text);
+        let wrap = |wrap_width| {
+            layout.compute_wrap_boundaries(
+                text,
+                px(wrap_width),
+                px(0.),
+                WrapMode::WordBoundaryThenAnywhere,
+                |_| px(10.),
+            )
+        };
+
+        // "ab cd-" is 60px wide, so it's only wrapped at the soft hyphen when that fits.
+        assert_eq!(
+            wrap(60.).as_slice(),
+            &[WrapBoundary {
+                run_ix: 0,
+                glyph_ix: 6
+            }]
+        );
+        assert_eq!(
+            wrap(55.).as_slice(),
+            &[WrapBoundary {
+                run_ix: 0,
+                glyph_ix: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_wrap_after_overflowing_word() {
+        // "abcdefg hi" with 10px wide glyphs, so the first word alone overflows the line.
+        let text = "abcdefg hi";
+        let layout = LineLayout {
+            font_size: px(16.),
+            width: px(100.),
+            ascent: px(12.),
+            descent: px(4.),
+            runs: vec![ShapedRun {
+                font_id: FontId(0),
+                glyphs: (0..10).map(|ix| glyph(ix, ix as f32 * 10.)).collect(),
+            }],
+            len: text.len(),
+        };
+
+        // The word isn't broken, but the line still wraps at the next word.
+        let boundaries =
+            layout.compute_wrap_boundaries(text, px(30.), px(0.), WrapMode::WordBoundary, |_| {
+                px(10.)
+            });
+        assert_eq!(
+            boundaries.as_slice(),
+            &[WrapBoundary {
+                run_ix: 0,
+                glyph_ix: 8
+            }]
+        );
+    }
+
+    #[test]
+    fn test_layout_cache_stats() {
+        let cache = test_cache(LineLayoutCacheBudget::default());
+        let layout_wrapped_line = || {
+            let runs = [FontRun {
+                len: "hello world".len(),
+                font_id: FontId(0),
+            }];
+            cache.layout_wrapped_line(
+                "hello world",
+                px(16.),
+                &runs,
+                Some(px(40.)),
+                None,
+                WrapMode::default(),
+            )
+        };
+
+        // The first call lays out both the unwrapped and the wrapped line.
+        layout_wrapped_line();
+        layout_wrapped_line();
+        let stats = cache.stats();
+        assert_eq!(
+            stats.current_frame,
+            LineLayoutCacheCounters {
+                hits: 1,
+                reused_from_previous_frame: 0,
+                shared_hits: 0,
+                misses: 2,
+            }
+        );
+        assert_eq!(stats.entries, 2);
+        assert!(stats.estimated_bytes > 0);
+
+        cache.finish_frame();
+        let stats = cache.stats();
+        assert_eq!(stats.current_frame, LineLayoutCacheCounters::default());
+        assert_eq!(stats.previous_frame.misses, 2);
+
+        layout_wrapped_line();
+        let stats = cache.stats();
+        assert_eq!(
+            stats.current_frame,
+            LineLayoutCacheCounters {
+                hits: 0,
+                reused_from_previous_frame: 1,
+                shared_hits: 0,
+                misses: 0,
+            }
+        );
+        assert_eq!(
+            stats.total,
+            LineLayoutCacheCounters {
+                hits: 1,
+                reused_from_previous_frame: 1,
+                shared_hits: 0,
+                misses: 2,
+            }
+        );
+        assert_eq!(stats.entries, 2);
+    }
+
+    #[test]
+    fn test_layout_cache_budget() {
+        let cache = test_cache(LineLayoutCacheBudget {
+            max_entries: Some(2),
+            max_bytes: None,
+        });
+
+        layout_line(&cache, "a");
+        layout_line(&cache, "b");
+        layout_line(&cache, "c");
+        // Layouts used during the current frame are never evicted.
+        assert_eq!(cache.stats().entries, 3);
+
+        cache.finish_frame();
+        layout_line(&cache, "d");
+        // Laying out "d" evicts the least recently used layouts from the previous frame.
+        assert_eq!(cache.stats().entries, 2);
+
+        let stats_before = cache.stats().current_frame;
+        layout_line(&cache, "c");
+        layout_line(&cache, "d");
+        layout_line(&cache, "a");
+        let stats = cache.stats();
+        assert_eq!(
+            stats.current_frame.reused_from_previous_frame,
+            stats_before.reused_from_previous_frame + 1
+        );
+        assert_eq!(stats.current_frame.hits, stats_before.hits + 1);
+        assert_eq!(stats.current_frame.misses, stats_before.misses + 1);
+        assert_eq!(stats.entries, 3);
+    }
+
+    #[test]
+    fn test_line_layouts_shared_between_windows() {
+        let shared_lines = Arc::new(SharedLineLayouts::default());
+        let first_window = LineLayoutCache::new(
+            Arc::new(TestTextSystem),
+            Arc::default(),
+            shared_lines.clone(),
+        );
+        let second_window =
+            LineLayoutCache::new(Arc::new(TestTextSystem), Arc::default(), shared_lines);
+
+        let first = layout_line(&first_window, "shared");
+        let second = layout_line(&second_window, "shared");
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(second_window.stats().current_frame.shared_hits, 1);
+        assert_eq!(second_window.stats().current_frame.misses, 0);
+
+        // Once no window uses a layout anymore, it isn't shared.
+        drop((first, second));
+        for window in [&first_window, &second_window] {
+            window.finish_frame();
+            window.finish_frame();
+        }
+        layout_line(&second_window, "shared");
+        assert_eq!(second_window.stats().current_frame.misses, 1);
+    }
+
+    #[test]
+    fn test_reuse_layouts_across_frames() {
+        let cache = test_cache(LineLayoutCacheBudget::default());
+
+        // Simulate an element that lays out "b" and "c" in the middle of a frame.
+        let a = layout_line(&cache, "a");
+        let start = cache.layout_index();
+        let b = layout_line(&cache, "b");
+        let c = layout_line(&cache, "c");
+        let end = cache.layout_index();
+        layout_line(&cache, "d");
+        cache.finish_frame();
+
+        // In the next frame, the element is reused without being laid out again.
+        let reused_start = cache.layout_index();
+        cache.reuse_layouts(start..end);
+        let reused_end = cache.layout_index();
+        assert!(Arc::ptr_eq(&b, &layout_line(&cache, "b")));
+        assert!(Arc::ptr_eq(&c, &layout_line(&cache, "c")));
+        let stats = cache.stats().current_frame;
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.reused_from_previous_frame, 0);
+        assert!(Arc::ptr_eq(&a, &layout_line(&cache, "a")));
+        cache.finish_frame();
+
+        // Reused layouts keep their order, so they can be reused again in the following frame.
+        cache.reuse_layouts(reused_start..reused_end);
+        assert!(Arc::ptr_eq(&b, &layout_line(&cache, "b")));
+        assert!(Arc::ptr_eq(&c, &layout_line(&cache, "c")));
+        assert_eq!(cache.stats().current_frame.hits, 2);
+
+        // "d" wasn't used in the previous frame, so it was dropped.
+        layout_line(&cache, "d");
+        assert_eq!(cache.stats().current_frame.misses, 1);
+    }
+
+    /// Wraps [`TestTextSystem`], but each `layout_line` call waits until another thread is in
+    /// `layout_line` too, giving up after a timeout. This shows whether two threads shape text at
+    /// the same time, without depending on how long shaping takes.
+    #[derive(Default)]
+    struct RendezvousTextSystem {
+        /// How many `layout_line` calls have started, and how many of them met another call.
+        calls: Mutex<(usize, usize)>,
+        call_started: Condvar,
+    }
+
+    impl RendezvousTextSystem {
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        fn overlapping_calls(&self) -> usize {
+            self.calls.lock().1
+        }
+    }
+
+    impl PlatformTextSystem for RendezvousTextSystem {
+        fn add_fonts(&self, fonts: Vec<Cow<'static, [u8]>>) -> Result<()> {
+            TestTextSystem.add_fonts(fonts)
+        }
+
+        fn all_font_names(&self) -> Vec<String> {
+            TestTextSystem.all_font_names()
+        }
+
+        fn all_font_families(&self) -> Vec<String> {
+            TestTextSystem.all_font_families()
+        }
+
+        fn font_id(&self, descriptor: &Font) -> Result<FontId> {
+            TestTextSystem.font_id(descriptor)
+        }
+
+        fn font_metrics(&self, font_id: FontId) -> FontMetrics {
+            TestTextSystem.font_metrics(font_id)
+        }
+
+        fn face_weight_and_style(&self, font_id: FontId) -> (FontWeight, FontStyle) {
+            TestTextSystem.face_weight_and_style(font_id)
+        }
+
+        fn typographic_bounds(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Bounds<f32>> {
+            TestTextSystem.typographic_bounds(font_id, glyph_id)
+        }
+
+        fn advance(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Size<f32>> {
+            TestTextSystem.advance(font_id, glyph_id)
+        }
+
+        fn glyph_for_char(&self, font_id: FontId, ch: char) -> Option<GlyphId> {
+            TestTextSystem.glyph_for_char(font_id, ch)
+        }
+
+        fn glyph_outline(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Vec<OutlineSegment>> {
+            TestTextSystem.glyph_outline(font_id, glyph_id)
+        }
+
+        fn glyph_raster_bounds(&self, params: &RenderGlyphParams) -> Result<Bounds<DevicePixels>> {
+            TestTextSystem.glyph_raster_bounds(params)
+        }
+
+        fn rasterize_glyph(
+            &self,
+            params: &RenderGlyphParams,
+            raster_bounds: Bounds<DevicePixels>,
+        ) -> Result<(Size<DevicePixels>, Vec<u8>)> {
+            TestTextSystem.rasterize_glyph(params, raster_bounds)
+        }
+
+        fn layout_line(&self, text: &str, font_size: Pixels, runs: &[FontRun]) -> LineLayout {
+            let deadline = Instant::now() + Self::TIMEOUT;
+            let mut calls = self.calls.lock();
+            calls.0 += 1;
+            self.call_started.notify_all();
+            while calls.0 < 2
+                && !self
+                    .call_started
+                    .wait_until(&mut calls, deadline)
+                    .timed_out()
+            {}
+            if calls.0 >= 2 {
+                calls.1 += 1;
+            }
+            drop(calls);
+
+            TestTextSystem.layout_line(text, font_size, runs)
+        }
+    }
+
+    fn layout_on_threads(cache: &Arc<LineLayoutCache>, texts: [&'static str; 2]) {
+        let threads = texts.map(|text| {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                layout_line(&cache, text);
+            })
+        });
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_layout_line_shapes_on_threads_concurrently() {
+        let text_system = Arc::new(RendezvousTextSystem::default());
+        let cache = Arc::new(LineLayoutCache::new(
+            text_system.clone(),
+            Arc::default(),
+            Arc::default(),
+        ));
+
+        // Neither thread waits for the other to finish shaping before it starts.
+        layout_on_threads(&cache, ["first line", "second line"]);
+        assert_eq!(text_system.overlapping_calls(), 2);
+        assert_eq!(cache.stats().current_frame.misses, 2);
+    }
+
+    #[test]
+    fn test_layout_line_on_threads_shaping_the_same_line() {
+        let text_system = Arc::new(RendezvousTextSystem::default());
+        let shared_lines = Arc::new(SharedLineLayouts::default());
+        let cache = Arc::new(LineLayoutCache::new(
+            text_system.clone(),
+            Arc::default(),
+            shared_lines.clone(),
+        ));
+
+        // Both threads shape the line, but only the first to finish counts as a miss. The other
+        // uses its layout.
+        layout_on_threads(&cache, ["same line", "same line"]);
+        let stats = cache.stats().current_frame;
+        assert_eq!((stats.misses, stats.hits), (1, 1));
+        assert_eq!(cache.stats().entries, 1);
+
+        // The layout that was kept is the one shared with other windows.
+        let other_window_cache = LineLayoutCache::new(text_system, Arc::default(), shared_lines);
+        layout_line(&other_window_cache, "same line");
+        assert_eq!(other_window_cache.stats().current_frame.shared_hits, 1);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{px, FontId, FontRun, Pixels, PlatformTextSystem};
+use crate::{px, FontId, FontRun, Pixels, PlatformTextSystem, SOFT_HYPHEN};
 use collections::HashMap;
 use std::{iter, sync::Arc};
 
@@ -7,6 +7,9 @@ pub struct LineWrapper {
     platform_text_system: Arc<dyn PlatformTextSystem>,
     pub(crate) font_id: FontId,
     pub(crate) font_size: Pixels,
+    /// The advance of every printable ASCII character, if the font is monospace. This lets
+    /// those characters be measured without shaping them.
+    pub(crate) monospace_advance: Option<Pixels>,
     cached_ascii_char_widths: [Option<Pixels>; 128],
     cached_other_char_widths: HashMap<char, Pixels>,
 }
@@ -24,16 +27,21 @@ impl LineWrapper {
             platform_text_system: text_system,
             font_id,
             font_size,
+            monospace_advance: None,
             cached_ascii_char_widths: [None; 128],
             cached_other_char_widths: HashMap::default(),
         }
     }
 
     /// Wrap a line of text to the given width with this wrapper's font and font size.
+    ///
+    /// Lines are broken at the opportunities given by the Unicode line breaking algorithm
+    /// (UAX #14), or at any character, depending on the given [`WrapMode`].
     pub fn wrap_line<'a>(
         &'a mut self,
         line: &'a str,
         wrap_width: Pixels,
+        wrap_mode: WrapMode,
     ) -> impl Iterator<Item = Boundary> + 'a {
         let mut width = px(0.);
         let mut first_non_whitespace_ix = None;
@@ -41,7 +49,9 @@ impl LineWrapper {
         let mut last_candidate_ix = 0;
         let mut last_candidate_width = px(0.);
         let mut last_wrap_ix = 0;
-        let mut prev_c = '\0';
+        let mut break_opportunities = unicode_linebreak::linebreaks(line)
+            .map(|(ix, _)| ix)
+            .peekable();
         let mut char_indices = line.char_indices();
         iter::from_fn(move || {
             for (ix, c) in char_indices.by_ref() {
@@ -49,7 +59,18 @@ impl LineWrapper {
                     continue;
                 }
 
-                if prev_c == ' ' && c != ' ' && first_non_whitespace_ix.is_some() {
+                while break_opportunities
+                    .next_if(|&break_ix| break_ix < ix)
+                    .is_some()
+                {}
+                let is_break_opportunity = break_opportunities.next_if_eq(&ix).is_some();
+                if is_break_opportunity
+                    && first_non_whitespace_ix.is_some()
+                    && wrap_mode != WrapMode::Anywhere
+                    // Breaking after a soft hyphen shows a hyphen, which has to fit too.
+                    && (!line[..ix].ends_with(SOFT_HYPHEN)
+                        || width + self.width_for_char('-') <= wrap_width)
+                {
                     last_candidate_ix = ix;
                     last_candidate_width = width;
                 }
@@ -60,7 +81,10 @@ impl LineWrapper {
 
                 let char_width = self.width_for_char(c);
                 width += char_width;
-                if width > wrap_width && ix > last_wrap_ix {
+                if width > wrap_width
+                    && ix > last_wrap_ix
+                    && (last_candidate_ix > 0 || wrap_mode != WrapMode::WordBoundary)
+                {
                     if let (None, Some(first_non_whitespace_ix)) = (indent, first_non_whitespace_ix)
                     {
                         indent = Some(
@@ -83,7 +107,6 @@ impl LineWrapper {
 
                     return Some(Boundary::new(last_wrap_ix, indent.unwrap_or(0)));
                 }
-                prev_c = c;
             }
 
             None
@@ -92,7 +115,9 @@ impl LineWrapper {
 
     #[inline(always)]
     fn width_for_char(&mut self, c: char) -> Pixels {
-        if (c as u32) < 128 {
+        if let Some(advance) = self.monospace_advance.filter(|_| matches!(c, ' '..='~')) {
+            advance
+        } else if (c as u32) < 128 {
             if let Some(cached_width) = self.cached_ascii_char_widths[c as usize] {
                 cached_width
             } else {
@@ -110,6 +135,10 @@ impl LineWrapper {
     }
 
     fn compute_width_for_char(&self, c: char) -> Pixels {
+        if c == SOFT_HYPHEN {
+            return px(0.);
+        }
+
         let mut buffer = [0; 4];
         let buffer = c.encode_utf8(&mut buffer);
         self.platform_text_system
@@ -125,7 +154,33 @@ impl LineWrapper {
     }
 }
 
-/// A boundary between two lines of text.
+/// Where a line of text may be broken when it is wrapped.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WrapMode {
+    /// Only break at word boundaries, letting words that are wider than the wrap width overflow.
+    WordBoundary,
+    /// Break at whichever character would overflow the wrap width, e.g. for terminal grids.
+    Anywhere,
+    /// Break at word boundaries, and in the middle of words that don't fit on a line by themselves.
+    #[default]
+    WordBoundaryThenAnywhere,
+}
+
+/// How [`crate::WindowTextSystem::shape_text`] wraps each line of text. The default doesn't
+/// wrap.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WrapOptions {
+    /// The width to wrap lines to, or `None` to leave them unwrapped.
+    pub width: Option<Pixels>,
+    /// Where the second and later visual lines of a wrapped line start. They are wrapped to
+    /// the width that remains.
+    pub indent: Option<Pixels>,
+    /// Where lines may be broken.
+    pub mode: WrapMode,
+}
+
+/// A boundary between two lines of text. Boundaries are always soft wraps, see
+/// [`crate::BreakReason::SoftWrap`], as the wrapper is given one line at a time.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Boundary {
     /// The index of the last character in a line
@@ -143,13 +198,12 @@ impl Boundary {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{font, TestAppContext, TestDispatcher};
+    use crate::{font, TestAppContext, TestDispatcher, TextSystem};
     #[cfg(target_os = "macos")]
     use crate::{TextRun, WindowTextSystem, WrapBoundary};
     use rand::prelude::*;
 
-    #[test]
-    fn test_wrap_line() {
+    fn build_text_system() -> Arc<TextSystem> {
         let dispatcher = TestDispatcher::new(StdRng::seed_from_u64(0));
         let cx = TestAppContext::new(dispatcher, None);
         cx.text_system()
@@ -159,64 +213,195 @@ mod tests {
             .unwrap()
             .into()])
             .unwrap();
-        let id = cx.text_system().font_id(&font("Zed Plex Mono")).unwrap();
+        cx.text_system().clone()
+    }
 
-        cx.update(|cx| {
-            let text_system = cx.text_system().clone();
-            let mut wrapper =
-                LineWrapper::new(id, px(16.), text_system.platform_text_system.clone());
-            assert_eq!(
-                wrapper
-                    .wrap_line("aa bbb cccc ddddd eeee", px(72.))
-                    .collect::<Vec<_>>(),
-                &[
-                    Boundary::new(7, 0),
-                    Boundary::new(12, 0),
-                    Boundary::new(18, 0)
-                ],
-            );
-            assert_eq!(
-                wrapper
-                    .wrap_line("aaa aaaaaaaaaaaaaaaaaa", px(72.0))
-                    .collect::<Vec<_>>(),
-                &[
-                    Boundary::new(4, 0),
-                    Boundary::new(11, 0),
-                    Boundary::new(18, 0)
-                ],
-            );
-            assert_eq!(
-                wrapper
-                    .wrap_line("     aaaaaaa", px(72.))
-                    .collect::<Vec<_>>(),
-                &[
-                    Boundary::new(7, 5),
-                    Boundary::new(9, 5),
-                    Boundary::new(11, 5),
-                ]
-            );
-            assert_eq!(
-                wrapper
-                    .wrap_line("                            ", px(72.))
-                    .collect::<Vec<_>>(),
-                &[
-                    Boundary::new(7, 0),
-                    Boundary::new(14, 0),
-                    Boundary::new(21, 0)
-                ]
-            );
-            assert_eq!(
-                wrapper
-                    .wrap_line("          aaaaaaaaaaaaaa", px(72.))
-                    .collect::<Vec<_>>(),
-                &[
-                    Boundary::new(7, 0),
-                    Boundary::new(14, 3),
-                    Boundary::new(18, 3),
-                    Boundary::new(22, 3),
-                ]
-            );
-        });
+    fn build_wrapper() -> LineWrapper {
+        let text_system = build_text_system();
+        let id = text_system.font_id(&font("Zed Plex Mono")).unwrap();
+        LineWrapper::new(id, px(16.), text_system.platform_text_system.clone())
+    }
+
+    #[test]
+    fn test_wrap_line() {
+        let mut wrapper = build_wrapper();
+        assert_eq!(
+            wrapper
+                .wrap_line(
+                    "aa bbb cccc ddddd eeee",
+                    px(72.),
+                    WrapMode::WordBoundaryThenAnywhere
+                )
+                .collect::<Vec<_>>(),
+            &[
+                Boundary::new(7, 0),
+                Boundary::new(12, 0),
+                Boundary::new(18, 0)
+            ],
+        );
+        assert_eq!(
+            wrapper
+                .wrap_line(
+                    "aaa aaaaaaaaaaaaaaaaaa",
+                    px(72.0),
+                    WrapMode::WordBoundaryThenAnywhere
+                )
+                .collect::<Vec<_>>(),
+            &[
+                Boundary::new(4, 0),
+                Boundary::new(11, 0),
+                Boundary::new(18, 0)
+            ],
+        );
+        assert_eq!(
+            wrapper
+                .wrap_line("     aaaaaaa", px(72.), WrapMode::WordBoundaryThenAnywhere)
+                .collect::<Vec<_>>(),
+            &[
+                Boundary::new(7, 5),
+                Boundary::new(9, 5),
+                Boundary::new(11, 5),
+            ]
+        );
+        assert_eq!(
+            wrapper
+                .wrap_line(
+                    "                            ",
+                    px(72.),
+                    WrapMode::WordBoundaryThenAnywhere
+                )
+                .collect::<Vec<_>>(),
+            &[
+                Boundary::new(7, 0),
+                Boundary::new(14, 0),
+                Boundary::new(21, 0)
+            ]
+        );
+        assert_eq!(
+            wrapper
+                .wrap_line(
+                    "          aaaaaaaaaaaaaa",
+                    px(72.),
+                    WrapMode::WordBoundaryThenAnywhere
+                )
+                .collect::<Vec<_>>(),
+            &[
+                Boundary::new(7, 0),
+                Boundary::new(14, 3),
+                Boundary::new(18, 3),
+                Boundary::new(22, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap_line_break_opportunities() {
+        let mut wrapper = build_wrapper();
+
+        // URLs break after slashes, not after the scheme's colon or inside the host.
+        assert_eq!(
+            wrapper
+                .wrap_line(
+                    "https://zed.dev/docs",
+                    px(100.),
+                    WrapMode::WordBoundaryThenAnywhere
+                )
+                .collect::<Vec<_>>(),
+            &[Boundary::new(8, 0), Boundary::new(16, 0)],
+        );
+
+        // A no-break space glues the words around it together.
+        assert_eq!(
+            wrapper
+                .wrap_line("aaaa\u{a0}bbb", px(72.), WrapMode::WordBoundaryThenAnywhere)
+                .collect::<Vec<_>>(),
+            &[Boundary::new(8, 0)],
+        );
+
+        // Wrapping at a soft hyphen shows a hyphen, so it's only chosen when the hyphen fits.
+        assert_eq!(
+            wrapper
+                .wrap_line("ab cd\u{ad}ef", px(58.), WrapMode::WordBoundaryThenAnywhere)
+                .collect::<Vec<_>>(),
+            &[Boundary::new(7, 0)],
+        );
+        assert_eq!(
+            wrapper
+                .wrap_line("ab cd\u{ad}ef", px(55.), WrapMode::WordBoundaryThenAnywhere)
+                .collect::<Vec<_>>(),
+            &[Boundary::new(3, 0)],
+        );
+
+        // Japanese text can break between most characters, but never before a full stop.
+        let text = "これは日本語の文章です。改行の位置を確認します。";
+        let boundaries = wrapper
+            .wrap_line(text, px(72.), WrapMode::WordBoundaryThenAnywhere)
+            .collect::<Vec<_>>();
+        assert!(!boundaries.is_empty());
+        for boundary in boundaries {
+            assert!(text.is_char_boundary(boundary.ix));
+            assert!(!text[boundary.ix..].starts_with('。'), "{boundary:?}");
+        }
+    }
+
+    #[test]
+    fn test_wrap_line_modes() {
+        let mut wrapper = build_wrapper();
+
+        // Seven characters fit on each line, whatever the word boundaries are.
+        assert_eq!(
+            wrapper
+                .wrap_line("aaaa aaaa aaaaaaaaaa", px(72.), WrapMode::Anywhere)
+                .collect::<Vec<_>>(),
+            &[Boundary::new(7, 0), Boundary::new(14, 0)],
+        );
+
+        assert_eq!(
+            wrapper
+                .wrap_line("aa bbbbbbbbbb cc", px(72.), WrapMode::WordBoundary)
+                .collect::<Vec<_>>(),
+            &[Boundary::new(3, 0), Boundary::new(14, 0)],
+        );
+        assert_eq!(
+            wrapper
+                .wrap_line(
+                    "aa bbbbbbbbbb cc",
+                    px(72.),
+                    WrapMode::WordBoundaryThenAnywhere
+                )
+                .collect::<Vec<_>>(),
+            &[Boundary::new(3, 0), Boundary::new(10, 0)],
+        );
+    }
+
+    #[test]
+    fn test_wrap_line_monospace_fast_path() {
+        let text_system = build_text_system();
+        let id = text_system.font_id(&font("Zed Plex Mono")).unwrap();
+        assert!(text_system.is_monospace(id));
+
+        let mut fast = text_system.line_wrapper(font("Zed Plex Mono"), px(16.));
+        assert!(fast.monospace_advance.is_some());
+        let mut slow = build_wrapper();
+
+        for text in [
+            "aa bbb cccc ddddd eeee",
+            "          aaaaaaaaaaaaaa",
+            "fn main() { println!(\"hello, world\"); }",
+            "let x = some_function_with_a_long_name(argument, another_argument);",
+        ] {
+            for wrap_mode in [
+                WrapMode::WordBoundary,
+                WrapMode::Anywhere,
+                WrapMode::WordBoundaryThenAnywhere,
+            ] {
+                assert_eq!(
+                    fast.wrap_line(text, px(72.), wrap_mode).collect::<Vec<_>>(),
+                    slow.wrap_line(text, px(72.), wrap_mode).collect::<Vec<_>>(),
+                    "{text:?} {wrap_mode:?}"
+                );
+            }
+        }
     }
 
     // For compatibility with the test macro
@@ -267,7 +452,10 @@ mod tests {
                         bold.with_len(1),
                         normal.with_len(7),
                     ],
-                    Some(px(72.)),
+                    WrapOptions {
+                        width: Some(px(72.)),
+                        ..Default::default()
+                    },
                 )
                 .unwrap();
 
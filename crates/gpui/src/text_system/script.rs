@@ -0,0 +1,81 @@
+/// A writing system that can be given its own font fallbacks.
+/// See [`crate::TextSystem::set_script_fallbacks`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnicodeScript {
+    /// Latin letters, including the Latin-1 and Latin Extended blocks.
+    Latin,
+    /// Greek, including polytonic Greek.
+    Greek,
+    /// Cyrillic, including its supplement and extensions.
+    Cyrillic,
+    /// Hebrew.
+    Hebrew,
+    /// Arabic, including its presentation forms.
+    Arabic,
+    /// Devanagari.
+    Devanagari,
+    /// Thai.
+    Thai,
+    /// Hangul syllables and jamo.
+    Hangul,
+    /// Japanese Hiragana.
+    Hiragana,
+    /// Japanese Katakana.
+    Katakana,
+    /// Han ideographs, as used in Chinese, Japanese and Korean text.
+    Han,
+    /// Pictographs from the main emoji blocks. This isn't a Unicode script, but emoji fonts
+    /// are configured in the same way.
+    Emoji,
+}
+
+impl UnicodeScript {
+    /// Returns the script the given character belongs to, if it is one of the supported scripts.
+    pub fn of(ch: char) -> Option<Self> {
+        let script = match ch as u32 {
+            0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F | 0x1E00..=0x1EFF => Self::Latin,
+            0x370..=0x3FF | 0x1F00..=0x1FFF => Self::Greek,
+            0x400..=0x52F | 0x2DE0..=0x2DFF | 0xA640..=0xA69F => Self::Cyrillic,
+            0x590..=0x5FF => Self::Hebrew,
+            0x600..=0x6FF | 0x750..=0x77F | 0x8A0..=0x8FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => {
+                Self::Arabic
+            }
+            0x900..=0x97F => Self::Devanagari,
+            0xE00..=0xE7F => Self::Thai,
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Self::Hangul,
+            0x3040..=0x309F => Self::Hiragana,
+            0x30A0..=0x30FF | 0x31F0..=0x31FF => Self::Katakana,
+            0x2E80..=0x2FDF
+            | 0x3005
+            | 0x3007
+            | 0x3021..=0x3029
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xF900..=0xFAFF
+            | 0x20000..=0x2FA1F => Self::Han,
+            0x2600..=0x27BF | 0x1F300..=0x1FAFF => Self::Emoji,
+            _ => return None,
+        };
+        Some(script)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_script_of() {
+        assert_eq!(UnicodeScript::of('a'), Some(UnicodeScript::Latin));
+        assert_eq!(UnicodeScript::of('λ'), Some(UnicodeScript::Greek));
+        assert_eq!(UnicodeScript::of('ж'), Some(UnicodeScript::Cyrillic));
+        assert_eq!(UnicodeScript::of('ب'), Some(UnicodeScript::Arabic));
+        assert_eq!(UnicodeScript::of('한'), Some(UnicodeScript::Hangul));
+        assert_eq!(UnicodeScript::of('ひ'), Some(UnicodeScript::Hiragana));
+        assert_eq!(UnicodeScript::of('カ'), Some(UnicodeScript::Katakana));
+        assert_eq!(UnicodeScript::of('直'), Some(UnicodeScript::Han));
+        assert_eq!(UnicodeScript::of('👍'), Some(UnicodeScript::Emoji));
+        assert_eq!(UnicodeScript::of(' '), None);
+        assert_eq!(UnicodeScript::of('1'), None);
+    }
+}
@@ -1,19 +1,19 @@
 use crate::{
     hash, point, prelude::*, px, size, transparent_black, Action, AnyDrag, AnyElement, AnyTooltip,
-    AnyView, AppContext, Arena, Asset, AsyncWindowContext, AvailableSpace, Bounds, BoxShadow,
-    Context, Corners, CursorStyle, DevicePixels, DispatchActionListener, DispatchNodeId,
+    AnyView, AppContext, Arena, Asset, AsyncWindowContext, AtlasKey, AvailableSpace, Bounds,
+    BoxShadow, Context, Corners, CursorStyle, DevicePixels, DispatchActionListener, DispatchNodeId,
     DispatchTree, DisplayId, Edges, Effect, Entity, EntityId, EventEmitter, FileDropEvent, Flatten,
-    FontId, Global, GlobalElementId, GlyphId, Hsla, ImageData, InputHandler, IsZero, KeyBinding,
-    KeyContext, KeyDownEvent, KeyEvent, KeyMatch, KeymatchResult, Keystroke, KeystrokeEvent,
-    LayoutId, LineLayoutIndex, Model, ModelContext, Modifiers, ModifiersChangedEvent,
-    MonochromeSprite, MouseButton, MouseEvent, MouseMoveEvent, MouseUpEvent, Path, Pixels,
-    PlatformAtlas, PlatformDisplay, PlatformInput, PlatformInputHandler, PlatformWindow, Point,
-    PolychromeSprite, PromptLevel, Quad, Render, RenderGlyphParams, RenderImageParams,
-    RenderSvgParams, ScaledPixels, Scene, Shadow, SharedString, Size, StrikethroughStyle, Style,
-    SubscriberSet, Subscription, TaffyLayoutEngine, Task, TextStyle, TextStyleRefinement,
-    TransformationMatrix, Underline, UnderlineStyle, View, VisualContext, WeakView,
-    WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowOptions, WindowParams,
-    WindowTextSystem, SUBPIXEL_VARIANTS,
+    Font, FontId, FontSynthesis, Global, GlobalElementId, GlyphId, Hsla, ImageData, InputHandler,
+    IsZero, KeyBinding, KeyContext, KeyDownEvent, KeyEvent, KeyMatch, KeymatchResult, Keystroke,
+    KeystrokeEvent, LayoutId, LineLayoutIndex, Model, ModelContext, Modifiers,
+    ModifiersChangedEvent, MonochromeSprite, MouseButton, MouseEvent, MouseMoveEvent, MouseUpEvent,
+    Path, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput, PlatformInputHandler,
+    PlatformWindow, Point, PolychromeSprite, PromptLevel, Quad, Render, RenderGlyphParams,
+    RenderImageParams, RenderSvgParams, ScaledPixels, Scene, Shadow, SharedString, Size,
+    StrikethroughStyle, Style, SubscriberSet, Subscription, TaffyLayoutEngine, Task,
+    TextRenderingMode, TextStyle, TextStyleRefinement, TransformationMatrix, Underline,
+    UnderlineStyle, View, VisualContext, WeakView, WindowAppearance, WindowBackgroundAppearance,
+    WindowBounds, WindowOptions, WindowParams, WindowTextSystem, SUBPIXEL_VARIANTS,
 };
 use anyhow::{anyhow, Context as _, Result};
 use collections::{FxHashMap, FxHashSet};
@@ -508,6 +508,7 @@ pub struct Window {
     sprite_atlas: Arc<dyn PlatformAtlas>,
     text_system: Arc<WindowTextSystem>,
     rem_size: Pixels,
+    text_rendering_mode: TextRenderingMode,
     /// The stack of override values for the window's rem size.
     ///
     /// This is used by `with_rem_size` to allow rendering an element tree with
@@ -520,6 +521,7 @@ pub struct Window {
     pub(crate) text_style_stack: Vec<TextStyleRefinement>,
     pub(crate) element_offset_stack: Vec<Point<Pixels>>,
     pub(crate) content_mask_stack: Vec<ContentMask<Pixels>>,
+    opacity: f32,
     pub(crate) requested_autoscroll: Option<Bounds<Pixels>>,
     pub(crate) rendered_frame: Frame,
     pub(crate) next_frame: Frame,
@@ -622,6 +624,15 @@ fn default_bounds(display_id: Option<DisplayId>, cx: &mut AppContext) -> Bounds<
         })
 }
 
+/// Rounds a line thickness to a whole number of device pixels, and at least one, so that thin
+/// underlines stay sharp instead of being blurred across two rows of pixels.
+fn snap_line_thickness(thickness: Pixels, scale_factor: f32) -> Pixels {
+    if thickness <= Pixels::ZERO {
+        return thickness;
+    }
+    px((thickness.0 * scale_factor).round().max(1.) / scale_factor)
+}
+
 impl Window {
     pub(crate) fn new(
         handle: AnyWindowHandle,
@@ -793,6 +804,7 @@ impl Window {
             sprite_atlas,
             text_system,
             rem_size: px(16.),
+            text_rendering_mode: TextRenderingMode::default(),
             rem_size_override_stack: SmallVec::new(),
             viewport_size: content_size,
             layout_engine: Some(TaffyLayoutEngine::new()),
@@ -801,6 +813,7 @@ impl Window {
             text_style_stack: Vec::new(),
             element_offset_stack: Vec::new(),
             content_mask_stack: Vec::new(),
+            opacity: 1.,
             requested_autoscroll: None,
             rendered_frame: Frame::new(DispatchTree::new(cx.keymap.clone(), cx.actions.clone())),
             next_frame: Frame::new(DispatchTree::new(cx.keymap.clone(), cx.actions.clone())),
@@ -1268,6 +1281,21 @@ impl<'a> WindowContext<'a> {
         self.window.scale_factor
     }
 
+    /// How glyphs are positioned and antialiased in this window.
+    pub fn text_rendering_mode(&self) -> TextRenderingMode {
+        self.window.text_rendering_mode
+    }
+
+    /// Sets how glyphs are positioned and antialiased in this window, and redraws it. Rasterized
+    /// glyphs are cached separately for each mode, so the next frame doesn't reuse glyphs that
+    /// were rasterized in the previous mode.
+    pub fn set_text_rendering_mode(&mut self, mode: TextRenderingMode) {
+        if self.window.text_rendering_mode != mode {
+            self.window.text_rendering_mode = mode;
+            self.refresh();
+        }
+    }
+
     /// The size of an em for the base font of the application. Adjusting this value allows the
     /// UI to scale, just like zooming a web page.
     pub fn rem_size(&self) -> Pixels {
@@ -2189,6 +2217,30 @@ impl<'a> WindowContext<'a> {
         });
     }
 
+    /// Invoke the given function with everything it paints faded by the given opacity, between
+    /// 0.0 and 1.0. This applies to every primitive, including text, decorations, and images,
+    /// so a block of text can be faded without restyling its runs. Nested opacities multiply.
+    ///
+    /// This method should only be called as part of the paint phase of element drawing.
+    pub fn with_opacity<R>(&mut self, opacity: f32, f: impl FnOnce(&mut Self) -> R) -> R {
+        debug_assert_eq!(
+            self.window.draw_phase,
+            DrawPhase::Paint,
+            "this method can only be called during paint"
+        );
+
+        let previous_opacity = self.window.opacity;
+        self.window.opacity *= opacity.clamp(0., 1.);
+        let result = f(self);
+        self.window.opacity = previous_opacity;
+        result
+    }
+
+    /// The opacity that everything painted at this point is faded by, see [`Self::with_opacity`].
+    pub fn opacity(&self) -> f32 {
+        self.window.opacity
+    }
+
     /// Creates a new painting layer for the specified bounds. A "layer" is a batch
     /// of geometry that are non-overlapping and have the same draw order. This is typically used
     /// for performance reasons.
@@ -2237,6 +2289,7 @@ impl<'a> WindowContext<'a> {
 
         let scale_factor = self.scale_factor();
         let content_mask = self.content_mask();
+        let opacity = self.window.opacity;
         for shadow in shadows {
             let mut shadow_bounds = bounds;
             shadow_bounds.origin += shadow.offset;
@@ -2247,7 +2300,7 @@ impl<'a> WindowContext<'a> {
                 bounds: shadow_bounds.scale(scale_factor),
                 content_mask: content_mask.scale(scale_factor),
                 corner_radii: corner_radii.scale(scale_factor),
-                color: shadow.color,
+                color: shadow.color.opacity(opacity),
             });
         }
     }
@@ -2266,13 +2319,14 @@ impl<'a> WindowContext<'a> {
 
         let scale_factor = self.scale_factor();
         let content_mask = self.content_mask();
+        let opacity = self.window.opacity;
         self.window.next_frame.scene.insert_primitive(Quad {
             order: 0,
             pad: 0,
             bounds: quad.bounds.scale(scale_factor),
             content_mask: content_mask.scale(scale_factor),
-            background: quad.background,
-            border_color: quad.border_color,
+            background: quad.background.opacity(opacity),
+            border_color: quad.border_color.opacity(opacity),
             corner_radii: quad.corner_radii.scale(scale_factor),
             border_widths: quad.border_widths.scale(scale_factor),
         });
@@ -2291,7 +2345,8 @@ impl<'a> WindowContext<'a> {
         let scale_factor = self.scale_factor();
         let content_mask = self.content_mask();
         path.content_mask = content_mask;
-        path.color = color.into();
+        let color: Hsla = color.into();
+        path.color = color.opacity(self.window.opacity);
         self.window
             .next_frame
             .scene
@@ -2314,10 +2369,11 @@ impl<'a> WindowContext<'a> {
         );
 
         let scale_factor = self.scale_factor();
+        let thickness = snap_line_thickness(style.thickness, scale_factor);
         let height = if style.wavy {
-            style.thickness * 3.
+            thickness * 3.
         } else {
-            style.thickness
+            thickness
         };
         let bounds = Bounds {
             origin,
@@ -2330,8 +2386,8 @@ impl<'a> WindowContext<'a> {
             pad: 0,
             bounds: bounds.scale(scale_factor),
             content_mask: content_mask.scale(scale_factor),
-            color: style.color.unwrap_or_default(),
-            thickness: style.thickness.scale(scale_factor),
+            color: style.color.unwrap_or_default().opacity(self.window.opacity),
+            thickness: thickness.scale(scale_factor),
             wavy: style.wavy,
         });
     }
@@ -2352,7 +2408,8 @@ impl<'a> WindowContext<'a> {
         );
 
         let scale_factor = self.scale_factor();
-        let height = style.thickness;
+        let thickness = snap_line_thickness(style.thickness, scale_factor);
+        let height = thickness;
         let bounds = Bounds {
             origin,
             size: size(width, height),
@@ -2364,8 +2421,8 @@ impl<'a> WindowContext<'a> {
             pad: 0,
             bounds: bounds.scale(scale_factor),
             content_mask: content_mask.scale(scale_factor),
-            thickness: style.thickness.scale(scale_factor),
-            color: style.color.unwrap_or_default(),
+            thickness: thickness.scale(scale_factor),
+            color: style.color.unwrap_or_default().opacity(self.window.opacity),
             wavy: false,
         });
     }
@@ -2376,6 +2433,8 @@ impl<'a> WindowContext<'a> {
     /// You should generally prefer to use the [`ShapedLine::paint`](crate::ShapedLine::paint) or
     /// [`WrappedLine::paint`](crate::WrappedLine::paint) methods in the [`TextSystem`](crate::TextSystem).
     /// This method is only useful if you need to paint a single glyph that has already been shaped.
+    /// `synthesis` fakes the styles the font has no face for, see
+    /// [`TextSystem::font_synthesis`](crate::TextSystem::font_synthesis).
     ///
     /// This method should only be called as part of the paint phase of element drawing.
     pub fn paint_glyph(
@@ -2384,7 +2443,36 @@ impl<'a> WindowContext<'a> {
         font_id: FontId,
         glyph_id: GlyphId,
         font_size: Pixels,
+        synthesis: FontSynthesis,
+        color: Hsla,
+    ) -> Result<()> {
+        self.paint_glyph_run(
+            origin,
+            font_id,
+            font_size,
+            synthesis,
+            color,
+            &[(glyph_id, Point::default())],
+        )
+    }
+
+    /// Paints a run of monochrome (non-emoji) glyphs that share a font, size, synthesis and color
+    /// into the scene for the next frame at the current z-index. Each glyph is given with its
+    /// position relative to `origin`, whose y component is the baseline.
+    ///
+    /// This is equivalent to calling [`Self::paint_glyph`] for each glyph, but the scale factor,
+    /// content mask and text system are only looked up once for the whole run, the sprite atlas
+    /// is locked once, and the sprites are added to the scene together.
+    ///
+    /// This method should only be called as part of the paint phase of element drawing.
+    pub fn paint_glyph_run(
+        &mut self,
+        origin: Point<Pixels>,
+        font_id: FontId,
+        font_size: Pixels,
+        synthesis: FontSynthesis,
         color: Hsla,
+        glyphs: &[(GlyphId, Point<Pixels>)],
     ) -> Result<()> {
         debug_assert_eq!(
             self.window.draw_phase,
@@ -2393,51 +2481,114 @@ impl<'a> WindowContext<'a> {
         );
 
         let scale_factor = self.scale_factor();
-        let glyph_origin = origin.scale(scale_factor);
-        let subpixel_variant = Point {
-            x: (glyph_origin.x.0.fract() * SUBPIXEL_VARIANTS as f32).floor() as u8,
-            y: (glyph_origin.y.0.fract() * SUBPIXEL_VARIANTS as f32).floor() as u8,
-        };
-        let params = RenderGlyphParams {
-            font_id,
-            glyph_id,
-            font_size,
-            subpixel_variant,
-            scale_factor,
-            is_emoji: false,
-        };
-
-        let raster_bounds = self.text_system().raster_bounds(&params)?;
-        if !raster_bounds.is_zero() {
-            let tile = self
-                .window
-                .sprite_atlas
-                .get_or_insert_with(&params.clone().into(), &mut || {
-                    let (size, bytes) = self.text_system().rasterize_glyph(&params)?;
-                    Ok(Some((size, Cow::Owned(bytes))))
-                })?
-                .expect("Callback above only errors or returns Some");
-            let bounds = Bounds {
-                origin: glyph_origin.map(|px| px.floor()) + raster_bounds.origin.map(Into::into),
-                size: tile.bounds.size.map(Into::into),
+        let content_mask = self.content_mask().scale(scale_factor);
+        let text_system = self.text_system().clone();
+        let rendering_mode = self.window.text_rendering_mode;
+        let color = color.opacity(self.window.opacity);
+        let mut visible_glyphs = Vec::with_capacity(glyphs.len());
+        for &(glyph_id, position) in glyphs {
+            let glyph_origin = (origin + position).scale(scale_factor);
+            let subpixel_variant = match rendering_mode {
+                TextRenderingMode::Subpixel => Point {
+                    x: (glyph_origin.x.0.fract() * SUBPIXEL_VARIANTS as f32).floor() as u8,
+                    y: (glyph_origin.y.0.fract() * SUBPIXEL_VARIANTS as f32).floor() as u8,
+                },
+                TextRenderingMode::Grayscale | TextRenderingMode::Aliased => Point::default(),
             };
-            let content_mask = self.content_mask().scale(scale_factor);
-            self.window
-                .next_frame
-                .scene
-                .insert_primitive(MonochromeSprite {
-                    order: 0,
-                    pad: 0,
-                    bounds,
-                    content_mask,
-                    color,
-                    tile,
-                    transformation: TransformationMatrix::unit(),
-                });
+            let params = RenderGlyphParams {
+                font_id,
+                glyph_id,
+                font_size,
+                subpixel_variant,
+                scale_factor,
+                is_emoji: false,
+                rendering_mode,
+                synthetic_bold: synthesis.bold,
+                synthetic_oblique: synthesis.oblique,
+            };
+
+            let raster_bounds = text_system.raster_bounds(&params)?;
+            if raster_bounds.is_zero() {
+                continue;
+            }
+            visible_glyphs.push((params, glyph_origin, raster_bounds));
         }
+
+        let keys = visible_glyphs
+            .iter()
+            .map(|(params, _, _)| AtlasKey::from(params.clone()))
+            .collect::<Vec<_>>();
+        let tiles = self
+            .window
+            .sprite_atlas
+            .get_or_insert_many(&keys, &mut |ix| {
+                let (size, bytes) = text_system.rasterize_glyph(&visible_glyphs[ix].0)?;
+                Ok(Some((size, Cow::Owned(bytes))))
+            })?;
+        let sprites =
+            visible_glyphs
+                .iter()
+                .zip(tiles)
+                .map(|((_, glyph_origin, raster_bounds), tile)| {
+                    let tile = tile.expect("Callback above only errors or returns Some");
+                    MonochromeSprite {
+                        order: 0,
+                        pad: 0,
+                        bounds: Bounds {
+                            origin: glyph_origin.map(|px| px.floor())
+                                + raster_bounds.origin.map(Into::into),
+                            size: tile.bounds.size.map(Into::into),
+                        },
+                        content_mask: content_mask.clone(),
+                        color,
+                        tile,
+                        transformation: TransformationMatrix::unit(),
+                    }
+                });
+        self.window
+            .next_frame
+            .scene
+            .insert_monochrome_sprites(sprites);
         Ok(())
     }
 
+    /// Rasterizes the glyphs for the given characters into this window's glyph atlas on a
+    /// background thread, so that the first frame that paints them doesn't have to, e.g. during
+    /// startup or after the font size changes. Glyphs are rasterized at the window's current scale
+    /// factor, in each of the horizontal and vertical subpixel positions they can be painted at,
+    /// and glyphs that are already in the atlas are skipped. Bold and italic glyphs that are
+    /// synthesized aren't prewarmed. The glyph atlas never evicts anything, so each call builds
+    /// at most 4096 rasters, enough for 256 glyphs with subpixel rendering. The remaining
+    /// characters aren't prewarmed.
+    pub fn prewarm_glyphs(
+        &self,
+        font: Font,
+        font_size: Pixels,
+        chars: impl IntoIterator<Item = char>,
+    ) -> Task<Result<()>> {
+        let text_system = self.app.text_system().clone();
+        let atlas = self.window.sprite_atlas.clone();
+        let scale_factor = self.scale_factor();
+        let rendering_mode = self.window.text_rendering_mode;
+        let chars = chars.into_iter().collect::<Vec<_>>();
+        self.app.background_executor().spawn(async move {
+            text_system.prewarm_glyphs(
+                &font,
+                font_size,
+                scale_factor,
+                rendering_mode,
+                atlas.as_ref(),
+                chars.into_iter(),
+            )
+        })
+    }
+
+    /// Rasterizes the glyphs for every printable ASCII character into this window's glyph atlas
+    /// on a background thread. See [`Self::prewarm_glyphs`].
+    pub fn prewarm_ascii_glyphs(&self, font: Font, font_size: Pixels) -> Task<Result<()>> {
+        self.prewarm_glyphs(font, font_size, ' '..='~')
+    }
+
     /// Paints an emoji glyph into the scene for the next frame at the current z-index.
     ///
     /// The y component of the origin is the baseline of the glyph.
@@ -2469,6 +2620,10 @@ impl<'a> WindowContext<'a> {
             subpixel_variant: Default::default(),
             scale_factor,
             is_emoji: true,
+            // Emojis are color bitmaps, which are rendered the same way in every mode.
+            rendering_mode: TextRenderingMode::default(),
+            synthetic_bold: None,
+            synthetic_oblique: None,
         };
 
         let raster_bounds = self.text_system().raster_bounds(&params)?;
@@ -2494,6 +2649,8 @@ impl<'a> WindowContext<'a> {
                 .insert_primitive(PolychromeSprite {
                     order: 0,
                     grayscale: false,
+                    opacity: self.window.opacity,
+                    pad: 0,
                     bounds,
                     corner_radii: Default::default(),
                     content_mask,
@@ -2551,7 +2708,7 @@ impl<'a> WindowContext<'a> {
                 pad: 0,
                 bounds,
                 content_mask,
-                color,
+                color: color.opacity(self.window.opacity),
                 tile,
                 transformation,
             });
@@ -2595,6 +2752,8 @@ impl<'a> WindowContext<'a> {
             .insert_primitive(PolychromeSprite {
                 order: 0,
                 grayscale,
+                opacity: self.window.opacity,
+                pad: 0,
                 bounds,
                 content_mask,
                 corner_radii,
@@ -3045,6 +3204,11 @@ impl<'a> WindowContext<'a> {
                 self.window.modifiers = scroll_wheel.modifiers;
                 PlatformInput::ScrollWheel(scroll_wheel)
             }
+            PlatformInput::PinchGesture(pinch) => {
+                self.window.mouse_position = pinch.position;
+                self.window.modifiers = pinch.modifiers;
+                PlatformInput::PinchGesture(pinch)
+            }
             // Translate dragging and dropping of external files from the operating system
             // to internal drag and drop events.
             PlatformInput::FileDrop(file_drop) => match file_drop {
@@ -4919,3 +5083,186 @@ pub fn outline(bounds: impl Into<Bounds<Pixels>>, border_color: impl Into<Hsla>)
         border_color: border_color.into(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::snap_line_thickness;
+    use crate::{
+        self as gpui, canvas, fill, point, px, red, size, FontId, FontSynthesis, GlyphId,
+        ScaledPixels, Styled, TestAppContext, WindowContext,
+    };
+    use std::{
+        cell::RefCell,
+        rc::Rc,
+        slice,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn test_snap_line_thickness() {
+        // 1.25px rounds to a whole device pixel at each scale factor.
+        assert_eq!(snap_line_thickness(px(1.25), 1.), px(1.));
+        assert_eq!(snap_line_thickness(px(1.25), 2.), px(1.5));
+
+        // Lines thinner than a device pixel are widened to one, so they don't disappear.
+        assert_eq!(snap_line_thickness(px(0.2), 1.), px(1.));
+        assert_eq!(snap_line_thickness(px(0.2), 2.), px(0.5));
+
+        // A zero thickness means no line.
+        assert_eq!(snap_line_thickness(px(0.), 2.), px(0.));
+    }
+
+    #[gpui::test]
+    fn test_nested_opacity(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let alphas = Rc::new(RefCell::new(Vec::new()));
+
+        cx.draw(point(px(0.), px(0.)), size(px(100.), px(100.)), {
+            let alphas = alphas.clone();
+            move |_| {
+                canvas(
+                    |_, _| {},
+                    move |bounds, _, cx| {
+                        let paint_quad = |cx: &mut gpui::WindowContext| {
+                            cx.paint_quad(fill(bounds, red()));
+                            let quad = cx.window.next_frame.scene.quads.last().unwrap();
+                            alphas.borrow_mut().push(quad.background.a);
+                        };
+                        cx.with_opacity(0.5, |cx| {
+                            paint_quad(cx);
+                            cx.with_opacity(0.5, &paint_quad);
+                            paint_quad(cx);
+                        });
+                        paint_quad(cx);
+                    },
+                )
+                .size_full()
+            }
+        });
+
+        assert_eq!(*alphas.borrow(), [0.5, 0.25, 0.5, 1.]);
+    }
+
+    #[gpui::test]
+    fn test_paint_glyph_run(cx: &mut TestAppContext) {
+        cx.use_test_text_system();
+        let cx = cx.add_empty_window();
+        let sprites = Rc::new(RefCell::new(Vec::new()));
+
+        cx.draw(point(px(0.), px(0.)), size(px(100.), px(100.)), {
+            let sprites = sprites.clone();
+            move |_| {
+                canvas(
+                    |_, _| {},
+                    move |_, _, cx| {
+                        // The test font advances 8px per character at 16px.
+                        let glyphs = "ab a"
+                            .chars()
+                            .enumerate()
+                            .map(|(ix, ch)| (GlyphId(ch as u32), point(px(8. * ix as f32), px(0.))))
+                            .collect::<Vec<_>>();
+                        cx.paint_glyph_run(
+                            point(px(0.), px(20.)),
+                            FontId(0),
+                            px(16.),
+                            FontSynthesis::default(),
+                            red(),
+                            &glyphs,
+                        )
+                        .unwrap();
+                        let scene = &cx.window.next_frame.scene;
+                        sprites
+                            .borrow_mut()
+                            .extend(scene.monochrome_sprites.iter().cloned());
+                    },
+                )
+                .size_full()
+            }
+        });
+
+        // The space has nothing to draw, and both `a`s are drawn from the same tile.
+        let sprites = sprites.borrow();
+        assert_eq!(sprites.len(), 3);
+        assert_eq!(sprites[0].tile, sprites[2].tile);
+        assert_ne!(sprites[0].tile, sprites[1].tile);
+        assert_eq!(
+            sprites[2].bounds.origin.x - sprites[0].bounds.origin.x,
+            ScaledPixels(48.)
+        );
+    }
+
+    /// Compares painting 20,000 glyphs one at a time with painting them as runs of a line's
+    /// length, with every tile already in the atlas. Run it with
+    /// `cargo test -p gpui --release -- --ignored --nocapture test_paint_glyph_run_performance`.
+    #[gpui::test]
+    #[ignore = "benchmark"]
+    fn test_paint_glyph_run_performance(cx: &mut TestAppContext) {
+        const LINES: usize = 200;
+        const GLYPHS_PER_LINE: usize = 100;
+        const FRAMES: u32 = 10;
+
+        cx.use_test_text_system();
+        let cx = cx.add_empty_window();
+        // The test font advances 8px per character at 16px.
+        let line = "The quick brown fox jumps over the lazy dog. "
+            .chars()
+            .cycle()
+            .take(GLYPHS_PER_LINE)
+            .enumerate()
+            .map(|(ix, ch)| (GlyphId(ch as u32), point(px(8. * ix as f32), px(0.))))
+            .collect::<Vec<_>>();
+        let line = Rc::new(line);
+        let mut time_frames = |batched: bool| {
+            let mut elapsed = Duration::ZERO;
+            // The first frame fills the atlas, and isn't timed.
+            for frame in 0..=FRAMES {
+                let line = line.clone();
+                let start = Instant::now();
+                cx.draw(
+                    point(px(0.), px(0.)),
+                    size(px(800.), px(4000.)),
+                    move |_| {
+                        canvas(
+                            |_, _| {},
+                            move |_, _, cx| {
+                                for line_ix in 0..LINES {
+                                    let origin = point(px(0.), px(20. * (line_ix + 1) as f32));
+                                    let paint = |cx: &mut WindowContext, glyphs| {
+                                        cx.paint_glyph_run(
+                                            origin,
+                                            FontId(0),
+                                            px(16.),
+                                            FontSynthesis::default(),
+                                            red(),
+                                            glyphs,
+                                        )
+                                        .unwrap()
+                                    };
+                                    if batched {
+                                        paint(cx, &line);
+                                    } else {
+                                        for glyph in line.iter() {
+                                            paint(cx, slice::from_ref(glyph));
+                                        }
+                                    }
+                                }
+                            },
+                        )
+                        .size_full()
+                    },
+                );
+                if frame > 0 {
+                    elapsed += start.elapsed();
+                }
+            }
+            elapsed / FRAMES
+        };
+
+        let per_glyph = time_frames(false);
+        let batched = time_frames(true);
+        println!(
+            "painting {} glyphs: {per_glyph:?} one at a time, {batched:?} in runs",
+            LINES * GLYPHS_PER_LINE
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Counts the heap allocations `WindowTextSystem::shape_text` makes for short single-run labels,
+//! the most common text in the UI. This is its own test binary because it installs a counting
+//! global allocator, which shouldn't affect other tests.
+
+use gpui::{
+    black, div, font, px, IntoElement, Render, SharedString, TestAppContext, TextRun, ViewContext,
+    WrapOptions,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// Counts the allocations made on each thread, so that work on other threads doesn't affect
+/// the count.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_allocation() {
+    // The thread local is gone while the thread shuts down.
+    ALLOCATIONS
+        .try_with(|count| count.set(count.get() + 1))
+        .ok();
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns how many allocations the current thread made while running `f`.
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let start = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - start
+}
+
+struct EmptyView;
+
+impl Render for EmptyView {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div()
+    }
+}
+
+const LABEL_COUNT: usize = 10_000;
+
+#[gpui::test]
+fn test_shape_single_run_labels_allocations(cx: &mut TestAppContext) {
+    cx.use_test_text_system();
+    let (_, cx) = cx.add_window_view(|_| EmptyView);
+    let text_system = cx.update(|cx| cx.text_system().clone());
+
+    let labels = (0..LABEL_COUNT)
+        .map(|ix| SharedString::from(format!("Label {ix}")))
+        .collect::<Vec<_>>();
+    let label_font = font("Zed Test");
+    let shape_labels = || {
+        for label in &labels {
+            let run = TextRun {
+                len: label.len(),
+                font: label_font.clone(),
+                color: black(),
+                background_color: None,
+                underline: None,
+                strikethrough: None,
+            };
+            text_system
+                .shape_text(label.clone(), px(14.), &[run], WrapOptions::default())
+                .unwrap();
+        }
+    };
+
+    // Laying a label out allocates its layouts and their cache keys.
+    let laying_out = count_allocations(shape_labels);
+    assert!(
+        laying_out <= 8 * LABEL_COUNT,
+        "laying out a label made {} allocations",
+        laying_out as f64 / LABEL_COUNT as f64
+    );
+
+    // A label that was already laid out this frame comes from the layout cache.
+    assert_eq!(count_allocations(shape_labels), 0);
+}
@@ -0,0 +1,182 @@
+//! Golden-image tests for text rendering. Each test draws some text with the deterministic test
+//! text system, renders the frame with `VisualTestContext::render_to_image`, and compares it
+//! against a reference PNG in `tests/golden`, allowing a small difference per channel.
+//!
+//! Run the tests with `UPDATE_GOLDEN_IMAGES=1` to write the references after an intentional
+//! rendering change, or when adding a test. Without it, a missing reference is a failure.
+
+use gpui::{
+    black, blue, div, font, px, red, rgb, size, white, Font, Hsla, IntoElement, ParentElement,
+    Pixels, Render, SharedString, Size, Styled, StyledText, TestAppContext, TextRun,
+    UnderlineStyle, ViewContext,
+};
+use image::RgbaImage;
+use std::{env, path::PathBuf};
+
+/// The largest difference allowed between a channel of a rendered pixel and the reference.
+const TOLERANCE: u8 = 2;
+
+/// Set to write the rendered images as the new references.
+const UPDATE_ENV_VAR: &str = "UPDATE_GOLDEN_IMAGES";
+
+struct TextView {
+    text: SharedString,
+    runs: Vec<TextRun>,
+    wrap_width: Option<Pixels>,
+}
+
+impl Render for TextView {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let text = StyledText::new(self.text.clone()).with_runs(self.runs.clone());
+        let mut container = div();
+        if let Some(wrap_width) = self.wrap_width {
+            container = container.w(wrap_width);
+        }
+
+        div()
+            .size_full()
+            .p(px(4.))
+            .bg(white())
+            .text_size(px(16.))
+            .line_height(px(20.))
+            .child(container.child(text))
+    }
+}
+
+fn run(len: usize, font: Font, color: Hsla) -> TextRun {
+    TextRun {
+        len,
+        font,
+        color,
+        background_color: None,
+        underline: None,
+        strikethrough: None,
+    }
+}
+
+/// Renders the view at the given size and compares it against the named reference image.
+fn assert_golden(name: &str, view: TextView, window_size: Size<Pixels>, cx: &mut TestAppContext) {
+    cx.use_test_text_system();
+    let (_, cx) = cx.add_window_view(|_| view);
+    let image = cx.render_to_image(window_size);
+
+    let golden_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let golden_path = golden_dir.join(format!("{name}.png"));
+    if env::var_os(UPDATE_ENV_VAR).is_some() {
+        std::fs::create_dir_all(&golden_dir).unwrap();
+        image.save(&golden_path).unwrap();
+        return;
+    }
+
+    let golden = match image::open(&golden_path) {
+        Ok(golden) => golden.into_rgba8(),
+        Err(error) => panic!(
+            "couldn't read the reference image {}: {error}. Run with {UPDATE_ENV_VAR}=1 to write it.",
+            golden_path.display(),
+        ),
+    };
+    if let Some(mismatch) = compare(&image, &golden) {
+        let actual_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.png"));
+        image.save(&actual_path).unwrap();
+        panic!(
+            "{name} doesn't match {}: {mismatch}. The rendered image was saved to {}. \
+             Run with {UPDATE_ENV_VAR}=1 if the change is intended.",
+            golden_path.display(),
+            actual_path.display(),
+        );
+    }
+}
+
+/// Describes the first difference between the images that exceeds the tolerance, if any.
+fn compare(actual: &RgbaImage, expected: &RgbaImage) -> Option<String> {
+    if actual.dimensions() != expected.dimensions() {
+        return Some(format!(
+            "the size is {:?} instead of {:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        ));
+    }
+
+    actual
+        .enumerate_pixels()
+        .zip(expected.pixels())
+        .find(|((_, _, actual), expected)| {
+            actual
+                .0
+                .iter()
+                .zip(expected.0)
+                .any(|(actual, expected)| actual.abs_diff(expected) > TOLERANCE)
+        })
+        .map(|((x, y, actual), expected)| {
+            format!(
+                "pixel ({x}, {y}) is {:?} instead of {:?}",
+                actual.0, expected.0
+            )
+        })
+}
+
+#[gpui::test]
+fn test_multi_run_styled_text(cx: &mut TestAppContext) {
+    let base = font("Zed Test");
+    let mut highlighted = run(11, base.clone(), black());
+    highlighted.background_color = Some(rgb(0xffe066).into());
+    let view = TextView {
+        text: "plain, bold, italic, red, highlighted".into(),
+        runs: vec![
+            run(7, base.clone(), black()),
+            run(4, base.clone().bold(), black()),
+            run(2, base.clone(), black()),
+            run(6, base.clone().italic(), black()),
+            run(2, base.clone(), black()),
+            run(3, base.clone(), red()),
+            run(2, base.clone(), black()),
+            highlighted,
+        ],
+        wrap_width: None,
+    };
+    assert_golden("multi_run_styled_text", view, size(px(320.), px(28.)), cx);
+}
+
+#[gpui::test]
+fn test_wrapped_text(cx: &mut TestAppContext) {
+    // Lines hold 15 characters, so the text is wrapped at spaces onto three lines.
+    let text = "wrapped text continues on the next line";
+    let view = TextView {
+        text: text.into(),
+        runs: vec![run(text.len(), font("Zed Test"), black())],
+        wrap_width: Some(px(120.)),
+    };
+    assert_golden("wrapped_text", view, size(px(136.), px(68.)), cx);
+}
+
+#[gpui::test]
+fn test_wavy_underline(cx: &mut TestAppContext) {
+    let mut misspelled = run(9, font("Zed Test"), black());
+    misspelled.underline = Some(UnderlineStyle {
+        color: Some(red()),
+        thickness: px(1.),
+        wavy: true,
+    });
+    let view = TextView {
+        text: "a misspeled word".into(),
+        runs: vec![
+            run(2, font("Zed Test"), black()),
+            misspelled,
+            run(5, font("Zed Test"), black()),
+        ],
+        wrap_width: None,
+    };
+    assert_golden("wavy_underline", view, size(px(144.), px(28.)), cx);
+}
+
+#[gpui::test]
+fn test_emoji_line(cx: &mut TestAppContext) {
+    // Emoji keep their own colors, so the blue text color shouldn't tint them.
+    let text = "hi 🎉 there 👋";
+    let view = TextView {
+        text: text.into(),
+        runs: vec![run(text.len(), font("Zed Test"), blue())],
+        wrap_width: None,
+    };
+    assert_golden("emoji_line", view, size(px(128.), px(28.)), cx);
+}
@@ -1,7 +1,7 @@
 use fuzzy::{StringMatch, StringMatchCandidate};
 use gpui::{
-    relative, AppContext, BackgroundExecutor, FontStyle, FontWeight, HighlightStyle, StyledText,
-    TextStyle, WhiteSpace,
+    relative, AppContext, BackgroundExecutor, FontStyle, FontWeight, FontWidth, HighlightStyle,
+    StyledText, TextStyle, WhiteSpace,
 };
 use settings::Settings;
 use std::ops::Range;
@@ -161,6 +161,7 @@ pub fn render_item<T>(
         font_size: settings.buffer_font_size(cx).into(),
         font_weight: FontWeight::NORMAL,
         font_style: FontStyle::Normal,
+        font_width: FontWidth::NORMAL,
         line_height: relative(1.),
         background_color: None,
         underline: None,
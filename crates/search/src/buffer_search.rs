@@ -14,7 +14,7 @@ use editor::{
 use futures::channel::oneshot;
 use gpui::{
     actions, div, impl_actions, Action, AppContext, ClickEvent, EventEmitter, FocusableView,
-    FontStyle, FontWeight, Hsla, InteractiveElement as _, IntoElement, KeyContext,
+    FontStyle, FontWeight, FontWidth, Hsla, InteractiveElement as _, IntoElement, KeyContext,
     ParentElement as _, Render, ScrollHandle, Styled, Subscription, Task, TextStyle, View,
     ViewContext, VisualContext as _, WhiteSpace, WindowContext,
 };
@@ -118,6 +118,7 @@ impl BufferSearchBar {
             font_size: rems(0.875).into(),
             font_weight: FontWeight::NORMAL,
             font_style: FontStyle::Normal,
+            font_width: FontWidth::NORMAL,
             line_height: relative(1.3),
             background_color: None,
             underline: None,
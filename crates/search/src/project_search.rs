@@ -1309,6 +1309,7 @@ impl ProjectSearchBar {
             font_size: rems(0.875).into(),
             font_weight: settings.buffer_font.weight,
             font_style: FontStyle::Normal,
+            font_width: settings.buffer_font.width,
             line_height: relative(1.3),
             background_color: None,
             underline: None,
@@ -621,6 +621,7 @@ impl Element for TerminalElement {
                     font_weight,
                     font_size: font_size.into(),
                     font_style: FontStyle::Normal,
+                    font_width: settings.buffer_font.width,
                     line_height: line_height.into(),
                     background_color: None,
                     white_space: WhiteSpace::Normal,
@@ -1249,7 +1249,7 @@ impl From<FontStyleContent> for FontStyle {
         match value {
             FontStyleContent::Normal => FontStyle::Normal,
             FontStyleContent::Italic => FontStyle::Italic,
-            FontStyleContent::Oblique => FontStyle::Oblique,
+            FontStyleContent::Oblique => FontStyle::Oblique(None),
         }
     }
 }
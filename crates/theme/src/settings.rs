@@ -3,8 +3,8 @@ use crate::{Appearance, SyntaxTheme, Theme, ThemeRegistry, ThemeStyleContent};
 use anyhow::Result;
 use derive_more::{Deref, DerefMut};
 use gpui::{
-    px, AppContext, Font, FontFeatures, FontStyle, FontWeight, Global, Pixels, Subscription,
-    ViewContext, WindowContext,
+    px, AppContext, Font, FontFeatures, FontStyle, FontWeight, FontWidth, Global, Pixels,
+    Subscription, ViewContext, WindowContext,
 };
 use refineable::Refineable;
 use schemars::{
@@ -232,18 +232,18 @@ pub struct ThemeSettingsContent {
     /// The OpenType features to enable for text in the UI.
     #[serde(default)]
     pub ui_font_features: Option<FontFeatures>,
-    /// The weight of the UI font in CSS units from 100 to 900.
+    /// The weight of the UI font in CSS units from 100 to 900, or a name such as "bold".
     #[serde(default)]
-    pub ui_font_weight: Option<f32>,
+    pub ui_font_weight: Option<FontWeight>,
     /// The name of a font to use for rendering in text buffers.
     #[serde(default)]
     pub buffer_font_family: Option<String>,
     /// The default font size for rendering in text buffers.
     #[serde(default)]
     pub buffer_font_size: Option<f32>,
-    /// The weight of the editor font in CSS units from 100 to 900.
+    /// The weight of the editor font in CSS units from 100 to 900, or a name such as "bold".
     #[serde(default)]
-    pub buffer_font_weight: Option<f32>,
+    pub buffer_font_weight: Option<FontWeight>,
     /// The buffer's line height.
     #[serde(default)]
     pub buffer_line_height: Option<BufferLineHeight>,
@@ -439,14 +439,16 @@ impl settings::Settings for ThemeSettings {
             ui_font: Font {
                 family: defaults.ui_font_family.clone().unwrap().into(),
                 features: defaults.ui_font_features.clone().unwrap(),
-                weight: defaults.ui_font_weight.map(FontWeight).unwrap(),
+                weight: defaults.ui_font_weight.unwrap(),
                 style: Default::default(),
+                width: Default::default(),
             },
             buffer_font: Font {
                 family: defaults.buffer_font_family.clone().unwrap().into(),
                 features: defaults.buffer_font_features.clone().unwrap(),
-                weight: defaults.buffer_font_weight.map(FontWeight).unwrap(),
+                weight: defaults.buffer_font_weight.unwrap(),
                 style: FontStyle::default(),
+                width: FontWidth::default(),
             },
             buffer_font_size: defaults.buffer_font_size.unwrap().into(),
             buffer_line_height: defaults.buffer_line_height.unwrap(),
@@ -472,7 +474,7 @@ impl settings::Settings for ThemeSettings {
             }
 
             if let Some(value) = value.buffer_font_weight {
-                this.buffer_font.weight = FontWeight(value);
+                this.buffer_font.weight = value;
             }
 
             if let Some(value) = value.ui_font_family.clone() {
@@ -482,7 +484,7 @@ impl settings::Settings for ThemeSettings {
                 this.ui_font.features = value;
             }
             if let Some(value) = value.ui_font_weight {
-                this.ui_font.weight = FontWeight(value);
+                this.ui_font.weight = value;
             }
 
             if let Some(value) = &value.theme {
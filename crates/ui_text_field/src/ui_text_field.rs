@@ -127,6 +127,7 @@ impl Render for TextField {
             font_size: rems(0.875).into(),
             font_weight: settings.buffer_font.weight,
             font_style: FontStyle::Normal,
+            font_width: settings.buffer_font.width,
             line_height: relative(1.2),
             color: style.text_color,
             ..Default::default()